@@ -0,0 +1,97 @@
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::document::{Format, TodoDocument};
+use crate::storage::Storage;
+use crate::{today, Todo};
+
+/// The archive of the todo file at `path`: `done.txt` next to `todo.txt`, as
+/// todo.txt tools expect, and `<name>.done.txt` next to any other list.
+pub(crate) fn path_for(path: &Path) -> PathBuf {
+    let name = match path.file_stem().and_then(|stem| stem.to_str()) {
+        Some("todo") | None => "done.txt".to_string(),
+        Some(stem) => format!("{}.done.txt", stem),
+    };
+    path.with_file_name(name)
+}
+
+/// Whether `todo` is ready for the archive: done, and with `min_age`, done
+/// for at least that many days. Without a completion date a task can only
+/// be archived by hand.
+pub(crate) fn is_archivable(todo: &Todo, min_age: Option<u32>) -> bool {
+    todo.done
+        && match min_age {
+            None => true,
+            Some(days) => todo.completed.is_some_and(|date| (today() - date).num_days() >= i64::from(days)),
+        }
+}
+
+/// Moves the tasks of `todos` that `select` picks to the end of `archive`,
+/// returning them with their index, in the order they were removed (last
+/// first). `todos` is only changed once the archive is saved, so a failure
+/// can't lose tasks.
+pub(crate) fn move_tasks(
+    archive: &Storage,
+    todos: &mut TodoDocument,
+    select: impl Fn(&Todo) -> bool,
+) -> io::Result<Vec<(usize, Todo)>> {
+    let indices: Vec<usize> = (0..todos.len()).filter(|&idx| select(&todos[idx])).collect();
+    if indices.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    archive.update(|done| -> io::Result<()> {
+        if done.is_empty() {
+            done.set_format(Format::TodoTxt);
+        }
+        for &idx in &indices {
+            let mut todo = todos[idx].clone();
+            // The archive records when each task was finished
+            todo.completed.get_or_insert_with(today);
+            done.insert(done.len(), todo);
+        }
+        Ok(())
+    })?;
+
+    Ok(indices.into_iter().rev().map(|idx| (idx, todos.remove(idx))).collect())
+}
+
+/// Takes `wanted` tasks out of `archive`, matching them by content since the
+/// archive may have changed since they were read. Returns the ones found.
+pub(crate) fn take(archive: &Storage, wanted: &[Todo]) -> io::Result<Vec<Todo>> {
+    archive.update(|done: &mut TodoDocument| -> io::Result<_> {
+        let mut taken = Vec::new();
+        for todo in wanted {
+            if let Some(idx) = done.iter().position(|t| t == todo) {
+                taken.push(done.remove(idx));
+            }
+        }
+        Ok(taken)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn archive_sits_next_to_its_list() {
+        assert_eq!(path_for(Path::new("/data/todo.txt")), Path::new("/data/done.txt"));
+        assert_eq!(path_for(Path::new("/data/work.txt")), Path::new("/data/work.done.txt"));
+    }
+
+    #[test]
+    fn only_old_enough_tasks_are_archived_automatically() {
+        let mut todo = Todo::from_input("Ship it");
+        assert!(!is_archivable(&todo, None));
+
+        todo.done = true;
+        assert!(is_archivable(&todo, None));
+        // Without a completion date only by hand
+        assert!(!is_archivable(&todo, Some(7)));
+
+        todo.completed = Some(today() - chrono::Duration::days(7));
+        assert!(is_archivable(&todo, Some(7)));
+        assert!(!is_archivable(&todo, Some(8)));
+    }
+}
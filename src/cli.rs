@@ -0,0 +1,536 @@
+use std::path::PathBuf;
+
+use serde::Serialize;
+
+use crate::archive;
+use crate::config::{Config, Overrides};
+use crate::document::Format;
+use crate::lists;
+use crate::storage::Storage;
+use crate::theme::{self, ThemeValue};
+use crate::{check_project_move, move_project, project_contains, today, Filter, ProjectFilter, Todo, TodoApp};
+
+type CliError = Box<dyn std::error::Error>;
+type CliResult = Result<(), CliError>;
+
+pub fn handle_cli_command(args: Vec<String>, config: &Config, config_warning: Option<&str>) -> CliResult {
+    if args.len() < 2 {
+        return Ok(()); // No CLI args, run GUI
+    }
+    if let Some(warning) = config_warning {
+        eprintln!("Warning: {}", warning);
+    }
+
+    let storage = || -> Result<Storage, CliError> { Ok(Storage::new(TodoApp::get_storage_path(config)?)) };
+    let rest = &args[2..];
+    match args[1].as_str() {
+        "add" => add(&storage()?, rest)?,
+        "list" | "ls" => list(&storage()?, rest)?,
+        "done" => set_done(&storage()?, rest, true)?,
+        "undone" => set_done(&storage()?, rest, false)?,
+        "rm" => remove(&storage()?, rest)?,
+        "edit" => edit(&storage()?, rest)?,
+        "mv" => move_task(&storage()?, rest)?,
+        "convert" => convert(&storage()?, rest)?,
+        "lists" => show_lists(&storage()?, rest)?,
+        "project" => project(&storage()?, rest)?,
+        "archive" => archive_done(&storage()?, rest)?,
+        "restore" => restore(&storage()?, rest)?,
+        "theme" => show_theme(config, rest)?,
+        "help" | "--help" | "-h" => print_help(),
+        _ => {
+            eprintln!("Unknown command: {}", args[1]);
+            eprintln!("Run 'omado help' for usage information.");
+            std::process::exit(1);
+        }
+    }
+
+    std::process::exit(0);
+}
+
+fn usage(text: &str) -> ! {
+    eprintln!("Usage: {}", text);
+    std::process::exit(1);
+}
+
+/// Removes the options accepted by the GUI and every command from `args`.
+/// They go before the command or among its options (`omado list --active
+/// --list work`); the command's first other argument or a `--` ends them, so
+/// task text is never taken for an option.
+pub fn take_overrides(args: &mut Vec<String>) -> Overrides {
+    const USAGE: &str = "omado [--list <name>|--file <path>] [--opacity <0.0-1.0>] [command]";
+
+    let mut overrides = Overrides::default();
+    let mut command = false;
+    let mut i = 1;
+    while i < args.len() {
+        if args[i] == "--" {
+            args.remove(i);
+            break;
+        }
+        match (args[i].as_str(), args.get(i + 1)) {
+            ("--file", Some(path)) => {
+                let path = PathBuf::from(path);
+                overrides.file = Some(std::env::current_dir().map(|dir| dir.join(&path)).unwrap_or(path));
+            }
+            ("--list", Some(name)) if lists::is_valid_name(name) => overrides.list = Some(name.clone()),
+            ("--opacity", Some(value)) => match value.parse::<f32>() {
+                Ok(opacity) if (0.0..=1.0).contains(&opacity) => overrides.opacity = Some(opacity),
+                _ => usage(USAGE),
+            },
+            // Without a value, as in `omado add --list`, it's the task text
+            ("--file" | "--list" | "--opacity", None) if command => break,
+            ("--file" | "--list" | "--opacity", _) => usage(USAGE),
+            // The command's own options, such as `list --active`, may come first
+            (arg, _) if command && !arg.starts_with('-') => break,
+            _ => {
+                command = true;
+                i += 1;
+                continue;
+            }
+        }
+        args.drain(i..i + 2);
+    }
+    overrides
+}
+
+/// Resolves a 1-based index as printed by `omado list` into a position in `todos`.
+fn parse_index(arg: &str, todos: &[Todo]) -> Result<usize, String> {
+    match arg.parse::<usize>() {
+        Ok(n) if n >= 1 && n <= todos.len() => Ok(n - 1),
+        Ok(n) => Err(format!("No task #{} (there are {} tasks)", n, todos.len())),
+        Err(_) => Err(format!("Invalid task number: {}", arg)),
+    }
+}
+
+fn add(storage: &Storage, args: &[String]) -> CliResult {
+    if args.is_empty() {
+        usage("omado add \"<task>\"");
+    }
+
+    let todo = Todo::from_input(&args[0]);
+
+    storage.update(|todos| -> CliResult {
+        todos.push(todo.clone());
+        Ok(())
+    })?;
+
+    // Confirmation message
+    if let Some(ref project) = todo.project {
+        println!("✓ Added task to project '{}': {}", project, todo.text);
+    } else {
+        println!("✓ Added task: {}", todo.text);
+    }
+    Ok(())
+}
+
+fn list(storage: &Storage, args: &[String]) -> CliResult {
+    const USAGE: &str = "omado list [--all|--active|--done|--archived|--due <today|overdue|upcoming>] [--project <name>|--no-project] [--search <text>]";
+
+    let mut filter = Filter::All;
+    let mut project_filter = ProjectFilter::All;
+    let mut search = String::new();
+
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--all" => filter = Filter::All,
+            "--active" => filter = Filter::Active,
+            "--done" => filter = Filter::Done,
+            "--archived" => filter = Filter::Archived,
+            "--due" => match args.next().and_then(|name| Filter::from_due_name(name)) {
+                Some(due) => filter = due,
+                None => usage(USAGE),
+            },
+            "--no-project" => project_filter = ProjectFilter::NoProject,
+            "--project" | "-p" => match args.next() {
+                Some(name) => project_filter = ProjectFilter::Project(name.clone()),
+                None => usage(USAGE),
+            },
+            "--search" | "-s" => match args.next() {
+                Some(text) => search = text.clone(),
+                None => usage(USAGE),
+            },
+            _ => usage(USAGE),
+        }
+    }
+
+    let todos = match filter {
+        Filter::Archived => Storage::new(archive::path_for(storage.path())).load()?,
+        _ => storage.load()?,
+    };
+    let width = todos.len().to_string().len();
+    let today = today();
+    let mut shown: Vec<_> = todos
+        .iter()
+        .enumerate()
+        .filter(|(_, todo)| filter.matches(todo, today) && project_filter.matches(todo) && TodoApp::matches_search(todo, &search))
+        .collect();
+    // Same order as the GUI: by priority, then file order
+    shown.sort_by_key(|(_, todo)| todo.priority_rank());
+    for (i, todo) in &shown {
+        let checkbox = if todo.done { "[x]" } else { "[ ]" };
+        println!("{:>width$} {} {}", i + 1, checkbox, todo.full_text(), width = width);
+    }
+
+    if shown.is_empty() {
+        println!("No matching tasks.");
+    }
+    Ok(())
+}
+
+fn set_done(storage: &Storage, args: &[String], done: bool) -> CliResult {
+    if args.len() != 1 {
+        usage(if done { "omado done <n>" } else { "omado undone <n>" });
+    }
+
+    let (idx, todo, changed, next) = storage.update(|todos| -> Result<_, CliError> {
+        let idx = parse_index(&args[0], todos)?;
+        // Completing a task again would move its completion date
+        if todos[idx].done == done {
+            return Ok((idx, todos[idx].clone(), false, None));
+        }
+        let next = todos.set_done(idx, done).map(|next| (next, todos[next].clone()));
+        Ok((idx, todos[idx].clone(), true, next))
+    })?;
+
+    if !changed {
+        let state = if done { "done" } else { "open" };
+        println!("Task {} is already {}: {}", idx + 1, state, todo.full_text());
+        return Ok(());
+    }
+    let verb = if done { "Completed" } else { "Reopened" };
+    println!("✓ {} task {}: {}", verb, idx + 1, todo.full_text());
+    if let Some((next_idx, next)) = next {
+        println!("↻ Next occurrence is task {}: {}", next_idx + 1, next.full_text());
+    }
+    Ok(())
+}
+
+fn remove(storage: &Storage, args: &[String]) -> CliResult {
+    if args.len() != 1 {
+        usage("omado rm <n>");
+    }
+
+    let (idx, todo) = storage.update(|todos| -> Result<_, CliError> {
+        let idx = parse_index(&args[0], todos)?;
+        Ok((idx, todos.remove(idx)))
+    })?;
+
+    println!("✓ Removed task {}: {}", idx + 1, todo.full_text());
+    Ok(())
+}
+
+fn edit(storage: &Storage, args: &[String]) -> CliResult {
+    if args.len() != 2 || args[1].trim().is_empty() {
+        usage("omado edit <n> \"<task>\"");
+    }
+
+    let (idx, todo) = storage.update(|todos| -> Result<_, CliError> {
+        let idx = parse_index(&args[0], todos)?;
+        todos[idx].set_input(&args[1]);
+        Ok((idx, todos[idx].clone()))
+    })?;
+
+    println!("✓ Updated task {}: {}", idx + 1, todo.full_text());
+    Ok(())
+}
+
+fn move_task(storage: &Storage, args: &[String]) -> CliResult {
+    if args.len() != 2 {
+        usage("omado mv <from> <to>");
+    }
+
+    let (to, todo) = storage.update(|todos| -> Result<_, CliError> {
+        let from = parse_index(&args[0], todos)?;
+        let to = parse_index(&args[1], todos)?;
+        todos.move_task(from, to);
+        Ok((to, todos[to].clone()))
+    })?;
+
+    println!("✓ Moved task to {}: {}", to + 1, todo.full_text());
+    Ok(())
+}
+
+fn convert(storage: &Storage, args: &[String]) -> CliResult {
+    let Some(format) = args.first().and_then(|name| Format::from_name(name)).filter(|_| args.len() == 1) else {
+        usage("omado convert <checklist|todo.txt>");
+    };
+
+    let from = storage.update(|todos| -> Result<_, CliError> {
+        let from = todos.format();
+        todos.set_format(format);
+        Ok(from)
+    })?;
+
+    println!("✓ Converted todo file from {} to {} format", from.name(), format.name());
+    Ok(())
+}
+
+fn archive_done(storage: &Storage, args: &[String]) -> CliResult {
+    if !args.is_empty() {
+        usage("omado archive");
+    }
+
+    let archive = Storage::new(archive::path_for(storage.path()));
+    let moved = storage.update(|todos| archive::move_tasks(&archive, todos, |todo| archive::is_archivable(todo, None)))?;
+    match moved.len() {
+        0 => println!("No completed tasks to archive."),
+        1 => println!("✓ Archived 1 task to {}", archive.path().display()),
+        n => println!("✓ Archived {} tasks to {}", n, archive.path().display()),
+    }
+    Ok(())
+}
+
+fn restore(storage: &Storage, args: &[String]) -> CliResult {
+    if args.len() != 1 {
+        usage("omado restore <n>");
+    }
+
+    let archive = Storage::new(archive::path_for(storage.path()));
+    let done = archive.load()?;
+    let todo = done[parse_index(&args[0], &done)?].clone();
+    // Into the todo file first, so that a failure leaves the task in both
+    // files rather than in neither
+    let idx = storage.update(|todos| -> Result<_, CliError> {
+        todos.push(todo.clone());
+        Ok(todos.len() - 1)
+    })?;
+    archive::take(&archive, std::slice::from_ref(&todo))?;
+
+    println!("✓ Restored task {}: {}", idx + 1, todo.full_text());
+    Ok(())
+}
+
+fn project(storage: &Storage, args: &[String]) -> CliResult {
+    const USAGE: &str = "omado project rename <old> <new> | merge <from> <into> | rm <name>";
+
+    let args: Vec<&str> = args.iter().map(String::as_str).collect();
+    match args.as_slice() {
+        ["rename", from, to] | ["merge", from, to] => {
+            let merge = args[0] == "merge";
+            let to = to.trim();
+            if to.is_empty() {
+                usage(USAGE);
+            }
+            let moved = storage.update(|todos| -> Result<_, CliError> {
+                check_project_move(todos, from, to, merge)?;
+                let mut moved = 0;
+                for todo in todos.iter_mut() {
+                    if let Some(project) = todo.project.as_deref().and_then(|p| move_project(p, from, to)) {
+                        todo.project = Some(project);
+                        moved += 1;
+                    }
+                }
+                Ok(moved)
+            })?;
+            lists::move_pins(storage, from, Some(to))?;
+
+            let (verb, preposition) = if merge { ("Merged", "into") } else { ("Renamed", "to") };
+            println!("✓ {} project '{}' {} '{}' ({} tasks)", verb, from, preposition, to, moved);
+        }
+        ["rm", name] => {
+            let removed = storage.update(|todos| -> Result<_, CliError> {
+                let indices: Vec<usize> = (0..todos.len())
+                    .filter(|&idx| todos[idx].project.as_deref().is_some_and(|p| project_contains(name, p)))
+                    .collect();
+                if indices.is_empty() {
+                    return Err(format!("No project '{}'", name).into());
+                }
+                for &idx in indices.iter().rev() {
+                    todos.remove(idx);
+                }
+                Ok(indices.len())
+            })?;
+            lists::move_pins(storage, name, None)?;
+
+            println!("✓ Removed project '{}' and its {} tasks", name, removed);
+        }
+        _ => usage(USAGE),
+    }
+    Ok(())
+}
+
+fn show_lists(storage: &Storage, args: &[String]) -> CliResult {
+    if !args.is_empty() {
+        usage("omado lists");
+    }
+
+    let current = lists::name_of(storage.path());
+    let mut names = lists::names();
+    if let Some(ref current) = current {
+        if !names.contains(current) {
+            names.push(current.clone());
+            names.sort();
+        }
+    }
+    for name in &names {
+        let open = Storage::new(lists::path(name)).load()?.iter().filter(|todo| !todo.done).count();
+        let marker = if current.as_ref() == Some(name) { "*" } else { " " };
+        println!("{} {} ({} open)", marker, name, open);
+    }
+    if current.is_none() {
+        println!("Using {} instead of a list", storage.path().display());
+    }
+    Ok(())
+}
+
+/// The theme as `omado theme --json` prints it.
+#[derive(Serialize)]
+struct ThemeReport<'a> {
+    setting: &'a str,
+    source: Option<&'static str>,
+    config_path: Option<PathBuf>,
+    files: Vec<ThemeFile>,
+    values: &'a [ThemeValue],
+    warnings: &'a [String],
+}
+
+#[derive(Serialize)]
+struct ThemeFile {
+    path: PathBuf,
+    exists: bool,
+}
+
+fn show_theme(config: &Config, args: &[String]) -> CliResult {
+    let json = match args {
+        [] => false,
+        [flag] if flag == "--json" => true,
+        _ => usage("omado theme [--json]"),
+    };
+
+    let loaded = theme::load(&config.theme);
+    let report = ThemeReport {
+        setting: &config.theme,
+        source: loaded.source.map(|source| source.name()),
+        config_path: loaded.source.and_then(|source| source.config_path()),
+        files: loaded.files.iter().map(|path| ThemeFile { path: path.clone(), exists: path.exists() }).collect(),
+        values: &loaded.values,
+        warnings: &loaded.warnings,
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+        return Ok(());
+    }
+
+    let (Some(source), Some(config_path)) = (report.source, &report.config_path) else {
+        println!("No terminal theme found (theme = \"{}\"), using omado's default colors", report.setting);
+        return Ok(());
+    };
+    println!("Theme: {} (theme = \"{}\")", source, report.setting);
+    println!("Config: {}", config_path.display());
+
+    println!();
+    println!("Files, in load order:");
+    for file in &report.files {
+        let missing = if file.exists { "" } else { " (missing)" };
+        println!("    {}{}", file.path.display(), missing);
+    }
+
+    println!();
+    if report.values.is_empty() {
+        println!("No values set, using omado's defaults");
+    } else {
+        println!("Values (anything else uses omado's defaults):");
+        let width = report.values.iter().map(|value| value.value.len()).max().unwrap_or(0);
+        for value in report.values {
+            println!(
+                "    {:<22} {:<width$}  {} in {}",
+                value.name,
+                value.value,
+                value.key,
+                value.file.display(),
+                width = width
+            );
+        }
+    }
+
+    if !report.warnings.is_empty() {
+        println!();
+        println!("Warnings:");
+        for warning in report.warnings {
+            println!("    {}", warning);
+        }
+    }
+    Ok(())
+}
+
+fn print_help() {
+    println!("omado - Simple todo management");
+    println!();
+    println!("USAGE:");
+    println!("    omado                        Launch GUI");
+    println!("    omado add \"<task>\"           Add a new task");
+    println!("    omado list [options]         List tasks with their numbers");
+    println!("    omado done <n>               Mark task <n> as done");
+    println!("    omado undone <n>             Mark task <n> as not done");
+    println!("    omado rm <n>                 Remove task <n>");
+    println!("    omado edit <n> \"<task>\"      Replace the text of task <n>");
+    println!("    omado mv <from> <to>         Move task <from> to position <to>");
+    println!("    omado convert <format>       Rewrite the todo file as checklist or todo.txt");
+    println!("    omado archive                Move completed tasks to the archive (done.txt)");
+    println!("    omado restore <n>            Move archived task <n> back to the todo file");
+    println!("    omado lists                  Show the todo lists, marking the one in use");
+    println!("    omado theme [--json]         Show where the theme is read from and what it sets");
+    println!("    omado project rename <old> <new>");
+    println!("                                 Move every task of project <old> and its subprojects to <new>");
+    println!("    omado project merge <from> <into>");
+    println!("                                 Move the tasks of <from> into the existing project <into>");
+    println!("    omado project rm <name>      Remove project <name>, its subprojects and all their tasks");
+    println!("    omado help                   Show this help");
+    println!();
+    println!("OPTIONS (GUI and every command):");
+    println!("    --list <name>                Use the list <name> instead of the active one");
+    println!("    --file <path>                Use <path> as the todo file");
+    println!("    --opacity <0.0-1.0>          Background opacity of the GUI");
+    println!();
+    println!("LIST OPTIONS:");
+    println!("    --all | --active | --done    Filter by completion (default: --all)");
+    println!("    --archived                   List the archive; numbers are for 'omado restore'");
+    println!("    --due <when>                 Open tasks due today, overdue, or upcoming (next 7 days)");
+    println!("    --project <name>             Only tasks in project <name> or its subprojects");
+    println!("    --no-project                 Only tasks without a project");
+    println!("    --search <text>              Only tasks containing <text>");
+    println!();
+    println!("EXAMPLES:");
+    println!("    omado add \"Buy groceries\"");
+    println!("    omado add \"work: Fix parser bug\"");
+    println!("    omado list --active --project work");
+    println!("    omado list --due today");
+    println!("    omado done 2");
+    println!("    omado add --list groceries \"Milk\"");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn take(args: &[&str]) -> (Vec<String>, Overrides) {
+        let mut args: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+        let overrides = take_overrides(&mut args);
+        (args, overrides)
+    }
+
+    #[test]
+    fn options_end_at_the_first_argument_of_the_command() {
+        let (args, overrides) = take(&["omado", "--list", "work", "add", "--opacity", "0.5", "Milk"]);
+        assert_eq!(args, ["omado", "add", "Milk"]);
+        assert_eq!(overrides.list.as_deref(), Some("work"));
+        assert_eq!(overrides.opacity, Some(0.5));
+
+        let (args, overrides) = take(&["omado", "edit", "2", "--opacity", "0.5"]);
+        assert_eq!(args, ["omado", "edit", "2", "--opacity", "0.5"]);
+        assert_eq!(overrides.opacity, None);
+
+        let (args, overrides) = take(&["omado", "list", "--active", "--list", "work"]);
+        assert_eq!(args, ["omado", "list", "--active"]);
+        assert_eq!(overrides.list.as_deref(), Some("work"));
+
+        let (args, _) = take(&["omado", "add", "--list"]);
+        assert_eq!(args, ["omado", "add", "--list"]);
+
+        let (args, overrides) = take(&["omado", "add", "--", "--list", "work"]);
+        assert_eq!(args, ["omado", "add", "--list", "work"]);
+        assert_eq!(overrides.list, None);
+    }
+}
@@ -0,0 +1,237 @@
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::theme::{self, ThemeSource};
+use crate::Filter;
+
+/// omado's own settings from `~/.config/omado/config.toml`. Every section is
+/// optional and a missing file means defaults.
+#[derive(Deserialize, Clone)]
+#[serde(default)]
+pub(crate) struct Config {
+    /// Todo file, `~/...` or relative to the config directory. Defaults to
+    /// the active list in `$XDG_DATA_HOME/omado`.
+    pub file: Option<PathBuf>,
+    /// List given with `--list`, instead of the active one.
+    #[serde(skip)]
+    pub list: Option<String>,
+    /// Background opacity, from 0.0 (transparent) to 1.0.
+    pub opacity: f32,
+    /// Filter shown at startup, by its name in the GUI.
+    pub default_filter: String,
+    /// Done tasks are archived when the GUI starts once they've been done
+    /// for this many days.
+    pub archive_after_days: Option<u32>,
+    /// Where colors and the font come from: `auto`, or one of the
+    /// `ThemeSource` names such as `kitty`.
+    pub theme: String,
+    pub window: WindowConfig,
+    pub font: FontConfig,
+    /// Key chord -> action name, applied on top of the default keymap.
+    pub keys: BTreeMap<String, String>,
+    /// Project name -> how it's shown, also used for its subprojects.
+    pub projects: BTreeMap<String, ProjectStyle>,
+}
+
+#[derive(Deserialize, Clone, Default)]
+#[serde(default)]
+pub(crate) struct ProjectStyle {
+    /// A palette color such as `red` or `bright.blue`, or a hex color.
+    /// Unset projects get a color picked from their name.
+    pub color: Option<String>,
+    /// Shown before the project name, e.g. an emoji or Nerd Font icon.
+    pub glyph: Option<String>,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub(crate) struct WindowConfig {
+    pub width: f32,
+    pub height: f32,
+    pub min_width: f32,
+    pub min_height: f32,
+}
+
+#[derive(Deserialize, Clone, Copy)]
+#[serde(default)]
+pub(crate) struct FontConfig {
+    /// Used when the theme doesn't set a font size.
+    pub size: f32,
+    /// Limits of zooming with `+`/`-`.
+    pub min_size: f32,
+    pub max_size: f32,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            file: None,
+            list: None,
+            opacity: 0.85,
+            default_filter: "all".to_string(),
+            archive_after_days: None,
+            theme: "auto".to_string(),
+            window: WindowConfig::default(),
+            font: FontConfig::default(),
+            keys: BTreeMap::new(),
+            projects: BTreeMap::new(),
+        }
+    }
+}
+
+impl Default for WindowConfig {
+    fn default() -> Self {
+        // The minimum width fits the ASCII art plus padding
+        Self { width: 520.0, height: 640.0, min_width: 480.0, min_height: 300.0 }
+    }
+}
+
+impl Default for FontConfig {
+    fn default() -> Self {
+        Self { size: 14.0, min_size: 8.0, max_size: 24.0 }
+    }
+}
+
+/// Settings given on the command line, which win over the config file.
+#[derive(Default, Clone)]
+pub(crate) struct Overrides {
+    pub file: Option<PathBuf>,
+    pub list: Option<String>,
+    pub opacity: Option<f32>,
+}
+
+impl Config {
+    pub fn path() -> Option<PathBuf> {
+        config_home().map(|dir| dir.join("omado").join("config.toml"))
+    }
+
+    pub fn load(path: &Path) -> Result<Self, String> {
+        let mut config: Config = match std::fs::read_to_string(path) {
+            Ok(content) => toml::from_str(&content).map_err(|e| format!("{}: {}", path.display(), e))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Config::default()),
+            Err(e) => return Err(format!("Couldn't read {}: {}", path.display(), e)),
+        };
+        if let (Some(file), Some(dir)) = (&config.file, path.parent()) {
+            let expanded = shellexpand::tilde(&file.to_string_lossy()).into_owned();
+            config.file = Some(dir.join(expanded));
+        }
+        Ok(config)
+    }
+
+    /// Reads the config file, if there is one, and applies `overrides`.
+    /// Settings the file gets wrong keep their defaults and are described in
+    /// the returned warning.
+    pub fn load_with(overrides: &Overrides) -> (Self, Option<String>) {
+        let Some(path) = Self::path() else { return (Self::default().with(overrides), None) };
+        let (config, problems) = match Self::load(&path) {
+            Ok(mut config) => {
+                let problems = config.validate();
+                (config, problems)
+            }
+            Err(e) => return (Self::default().with(overrides), Some(e)),
+        };
+        let warning = (!problems.is_empty()).then(|| format!("{}: {}", path.display(), problems.join("; ")));
+        (config.with(overrides), warning)
+    }
+
+    fn with(mut self, overrides: &Overrides) -> Self {
+        if let Some(ref list) = overrides.list {
+            self.file = None;
+            self.list = Some(list.clone());
+        }
+        if let Some(ref file) = overrides.file {
+            self.file = Some(file.clone());
+        }
+        if let Some(opacity) = overrides.opacity {
+            self.opacity = opacity;
+        }
+        self
+    }
+
+    /// Resets out-of-range settings to their defaults and describes them.
+    fn validate(&mut self) -> Vec<String> {
+        let defaults = Config::default();
+        let mut problems = Vec::new();
+
+        if !(0.0..=1.0).contains(&self.opacity) {
+            problems.push(format!("opacity {} isn't between 0.0 and 1.0", self.opacity));
+            self.opacity = defaults.opacity;
+        }
+        if Filter::from_name(&self.default_filter).is_none() {
+            problems.push(format!("unknown default_filter \"{}\"", self.default_filter));
+            self.default_filter = defaults.default_filter;
+        }
+        if !self.theme.eq_ignore_ascii_case("auto") && ThemeSource::from_name(&self.theme).is_none() {
+            problems.push(format!("unknown theme \"{}\"", self.theme));
+            self.theme = defaults.theme;
+        }
+
+        let window = self.window;
+        if [window.width, window.height, window.min_width, window.min_height].iter().any(|v| v.is_nan() || *v <= 0.0) {
+            problems.push("[window] sizes must be positive".to_string());
+            self.window = defaults.window;
+        }
+
+        let font = self.font;
+        if font.min_size.is_nan() || font.max_size.is_nan() || font.min_size <= 0.0 || font.min_size > font.max_size {
+            problems.push(format!("[font] min_size {} and max_size {} aren't a valid range", font.min_size, font.max_size));
+            self.font = defaults.font;
+        } else if !(font.min_size..=font.max_size).contains(&font.size) {
+            problems.push(format!("[font] size {} isn't between min_size and max_size", font.size));
+            self.font.size = font.size.clamp(font.min_size, font.max_size);
+        }
+
+        for (project, style) in &mut self.projects {
+            if let Some(color) = style.color.as_deref().filter(|color| !theme::is_color(color)) {
+                problems.push(format!("[projects] unknown color \"{}\" for {}", color, project));
+                style.color = None;
+            }
+        }
+        problems
+    }
+}
+
+/// `$XDG_CONFIG_HOME`, falling back to `~/.config`.
+pub(crate) fn config_home() -> Option<PathBuf> {
+    match std::env::var("XDG_CONFIG_HOME") {
+        Ok(dir) if !dir.is_empty() => Some(PathBuf::from(dir)),
+        _ => std::env::var("HOME").ok().map(|home| PathBuf::from(home).join(".config")),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn invalid_settings_fall_back_to_defaults() {
+        let mut config: Config = toml::from_str(
+            "opacity = 1.5\ndefault_filter = \"Upcoming\"\n[font]\nsize = 30.0\n[window]\nwidth = 600.0\n",
+        )
+        .unwrap();
+        let problems = config.validate();
+
+        assert_eq!(problems.len(), 2, "{:?}", problems);
+        assert_eq!(config.opacity, 0.85);
+        assert_eq!(config.default_filter, "Upcoming");
+        assert_eq!(config.font.size, 24.0);
+        assert_eq!((config.window.width, config.window.height), (600.0, 640.0));
+    }
+
+    #[test]
+    fn unknown_project_colors_are_dropped() {
+        let mut config: Config = toml::from_str(
+            "[projects]\nwork = { color = \"bright.red\", glyph = \"W\" }\nhome = { color = \"#a6e3a1\" }\nfun = { color = \"pink\", glyph = \"F\" }\n",
+        )
+        .unwrap();
+        let problems = config.validate();
+
+        assert_eq!(problems.len(), 1, "{:?}", problems);
+        assert_eq!(config.projects["work"].color.as_deref(), Some("bright.red"));
+        assert_eq!(config.projects["home"].color.as_deref(), Some("#a6e3a1"));
+        assert_eq!(config.projects["fun"].color, None);
+        assert_eq!(config.projects["fun"].glyph.as_deref(), Some("F"));
+    }
+}
@@ -0,0 +1,687 @@
+use std::ops::{Deref, DerefMut};
+
+use chrono::NaiveDate;
+
+use crate::{split_priority, today, Todo, Word};
+
+/// On-disk syntax of a todo file.
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub(crate) enum Format {
+    /// omado's original `[ ] project: text` checklist.
+    #[default]
+    Checklist,
+    /// The todo.txt format (`x 2026-10-16 2026-10-01 text +project @context key:value`),
+    /// shared with other todo.txt tools.
+    TodoTxt,
+}
+
+impl Format {
+    pub fn name(self) -> &'static str {
+        match self {
+            Format::Checklist => "checklist",
+            Format::TodoTxt => "todo.txt",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "checklist" => Some(Format::Checklist),
+            "todo.txt" | "todotxt" => Some(Format::TodoTxt),
+            _ => None,
+        }
+    }
+
+    /// Guesses the format of a file omado hasn't saved yet by counting lines
+    /// only one of the formats would write: `[ ]` lines against lines with a
+    /// todo.txt completion mark, priority, date or `+project`. Plain lines
+    /// could be either, so a file of notes has no format. Returns `None` for
+    /// files without evidence either way.
+    fn detect(content: &str) -> Option<Self> {
+        let checklist = content.lines().filter(|line| parse_checklist_line(line).is_some()).count();
+        let todotxt = content
+            .lines()
+            .filter_map(parse_todotxt_line)
+            .filter(|todo| todo.done || todo.priority.is_some() || todo.created.is_some() || todo.project.is_some())
+            .count();
+        if todotxt > checklist {
+            Some(Format::TodoTxt)
+        } else if checklist > 0 {
+            Some(Format::Checklist)
+        } else {
+            None
+        }
+    }
+}
+
+/// In-memory view of a todo file that remembers everything it does not
+/// understand, so rewriting the file only touches the tasks that changed.
+///
+/// Derefs to the parsed tasks; elements can be edited in place, while adding,
+/// removing and reordering tasks goes through [`TodoDocument::push`],
+/// [`TodoDocument::insert`], [`TodoDocument::remove`] and
+/// [`TodoDocument::move_task`] so the file layout stays in sync.
+#[derive(Clone)]
+pub(crate) struct TodoDocument {
+    todos: Vec<Todo>,
+    // Parallel to `todos`: the line a task was read from, if any.
+    sources: Vec<Option<Source>>,
+    lines: Vec<Line>,
+    line_ending: &'static str,
+    format: Format,
+}
+
+#[derive(Clone)]
+struct Source {
+    // Raw line including its terminator.
+    raw: String,
+    parsed: Todo,
+}
+
+#[derive(Clone)]
+enum Line {
+    Task(usize),
+    Other(String),
+}
+
+impl Default for TodoDocument {
+    /// An empty file, as for a todo file that doesn't exist yet.
+    fn default() -> Self {
+        TodoDocument {
+            todos: Vec::new(),
+            sources: Vec::new(),
+            lines: Vec::new(),
+            line_ending: "\n",
+            format: Format::default(),
+        }
+    }
+}
+
+impl TodoDocument {
+    /// Reads a file whose format isn't known, guessing it from the content.
+    ///
+    /// Checklist files omado hasn't saved yet may come from versions that
+    /// read `[ ] work:fix login` as project `work`; such lines keep that
+    /// reading and are rewritten as `[ ] work: fix login` on the next save.
+    pub fn parse(content: &str) -> Self {
+        let format = Format::detect(content).unwrap_or_default();
+        let mut doc = Self::parse_as(content, format);
+        if format == Format::Checklist {
+            for idx in 0..doc.todos.len() {
+                let legacy = doc.sources[idx].as_ref().and_then(|source| parse_legacy_checklist_line(&source.raw));
+                if let Some(todo) = legacy {
+                    doc.todos[idx] = todo;
+                    doc.sources[idx] = None;
+                }
+            }
+        }
+        doc
+    }
+
+    pub fn parse_as(content: &str, format: Format) -> Self {
+        let mut doc = TodoDocument {
+            line_ending: if content.contains("\r\n") { "\r\n" } else { "\n" },
+            format,
+            ..Default::default()
+        };
+
+        for raw in content.split_inclusive('\n') {
+            let parsed = match format {
+                Format::Checklist => parse_checklist_line(raw),
+                Format::TodoTxt => parse_todotxt_line(raw),
+            };
+            match parsed {
+                Some(todo) => {
+                    doc.lines.push(Line::Task(doc.todos.len()));
+                    doc.sources.push(Some(Source {
+                        raw: raw.to_string(),
+                        parsed: todo.clone(),
+                    }));
+                    doc.todos.push(todo);
+                }
+                None => doc.lines.push(Line::Other(raw.to_string())),
+            }
+        }
+        doc
+    }
+
+    /// Renders the file, reusing the original text of every line whose task
+    /// has not been modified since it was parsed.
+    pub fn contents(&self) -> String {
+        let mut out = String::new();
+        for line in &self.lines {
+            if !out.is_empty() && !out.ends_with('\n') {
+                out.push_str(self.line_ending);
+            }
+            match line {
+                Line::Other(raw) => out.push_str(raw),
+                Line::Task(idx) => match &self.sources[*idx] {
+                    Some(source) if source.parsed == self.todos[*idx] => out.push_str(&source.raw),
+                    _ => {
+                        out.push_str(&self.format_line(&self.todos[*idx]));
+                        out.push_str(self.line_ending);
+                    }
+                },
+            }
+        }
+        out
+    }
+
+    pub fn format(&self) -> Format {
+        self.format
+    }
+
+    /// Switches the file to `format`; every task is rewritten on the next save.
+    pub fn set_format(&mut self, format: Format) {
+        self.format = format;
+        self.sources.iter_mut().for_each(|source| *source = None);
+    }
+
+    fn format_line(&self, todo: &Todo) -> String {
+        match self.format {
+            Format::Checklist => format_checklist_line(todo),
+            Format::TodoTxt => format_todotxt_line(todo),
+        }
+    }
+
+    pub fn push(&mut self, mut todo: Todo) {
+        // todo.txt records when tasks were added
+        if self.format == Format::TodoTxt && todo.created.is_none() {
+            todo.created = Some(today());
+        }
+        self.lines.push(Line::Task(self.todos.len()));
+        self.sources.push(None);
+        self.todos.push(todo);
+    }
+
+    /// Inserts `todo` as task `idx`, placing its line right before the
+    /// current task `idx` (or after the last task).
+    pub fn insert(&mut self, idx: usize, todo: Todo) {
+        let at = self
+            .lines
+            .iter()
+            .position(|line| matches!(line, Line::Task(i) if *i == idx))
+            .or_else(|| {
+                self.lines
+                    .iter()
+                    .rposition(|line| matches!(line, Line::Task(_)))
+                    .map(|last| last + 1)
+            })
+            .unwrap_or(self.lines.len());
+        for line in &mut self.lines {
+            if let Line::Task(i) = line {
+                if *i >= idx {
+                    *i += 1;
+                }
+            }
+        }
+        self.lines.insert(at, Line::Task(idx));
+        self.sources.insert(idx, None);
+        self.todos.insert(idx, todo);
+    }
+
+    /// Moves task `from` so that it ends up at index `to`. Tasks trade places
+    /// while other lines stay where they are; each task keeps its original text.
+    pub fn move_task(&mut self, from: usize, to: usize) {
+        // Task lines are numbered in file order, so reordering the parallel
+        // vectors is enough
+        let todo = self.todos.remove(from);
+        self.todos.insert(to, todo);
+        let source = self.sources.remove(from);
+        self.sources.insert(to, source);
+    }
+
+    /// Completes or reopens a task. Completing a recurring task also appends
+    /// its next occurrence, whose index is returned, unless an open copy of
+    /// the task is already waiting, as when it is completed a second time.
+    pub fn set_done(&mut self, idx: usize, done: bool) -> Option<usize> {
+        self.todos[idx].set_done(done);
+        let next = self.todos[idx].next_occurrence().filter(|_| done)?;
+        let rule = |todo: &Todo| todo.tags.iter().find(|(key, _)| key == "rec").cloned();
+        let waiting = self
+            .todos
+            .iter()
+            .any(|todo| !todo.done && todo.project == next.project && todo.text == next.text && rule(todo) == rule(&next));
+        if waiting {
+            return None;
+        }
+        self.push(next);
+        Some(self.todos.len() - 1)
+    }
+
+    pub fn remove(&mut self, idx: usize) -> Todo {
+        self.lines.retain(|line| !matches!(line, Line::Task(i) if *i == idx));
+        for line in &mut self.lines {
+            if let Line::Task(i) = line {
+                if *i > idx {
+                    *i -= 1;
+                }
+            }
+        }
+        self.sources.remove(idx);
+        self.todos.remove(idx)
+    }
+}
+
+impl Deref for TodoDocument {
+    type Target = [Todo];
+
+    fn deref(&self) -> &[Todo] {
+        &self.todos
+    }
+}
+
+impl DerefMut for TodoDocument {
+    fn deref_mut(&mut self) -> &mut [Todo] {
+        &mut self.todos
+    }
+}
+
+fn parse_checklist_line(raw: &str) -> Option<Todo> {
+    let line = raw.trim();
+    let (done, rest) = if let Some(rest) = line.strip_prefix("[ ] ") {
+        (false, rest)
+    } else if let Some(rest) = line.strip_prefix("[x] ").or_else(|| line.strip_prefix("[X] ")) {
+        (true, rest)
+    } else {
+        return None;
+    };
+
+    let mut todo = Todo::default();
+    todo.set_full_text(rest);
+    todo.done = done;
+    // Completed tasks record when as a trailing done: tag; on open tasks it's
+    // just a tag
+    if let Some(pos) = todo.tags.iter().rposition(|(k, v)| k == "done" && split_date(v).is_some()).filter(|_| done) {
+        let (_, value) = todo.remove_tag(pos);
+        todo.completed = split_date(&value).map(|(date, _)| date);
+    }
+    Some(todo)
+}
+
+/// Reads a line whose first word is `project:text` with the project split
+/// off at the colon, as omado did before `key:value` tags. `None` unless the
+/// line is a task that reads differently that way.
+fn parse_legacy_checklist_line(raw: &str) -> Option<Todo> {
+    if parse_checklist_line(raw)?.project.is_some() {
+        return None;
+    }
+    // The checkbox is four ASCII bytes, as the line parsed
+    let (checkbox, rest) = raw.trim().split_at(4);
+    let (priority, rest) = split_priority(rest);
+    let (project, text) = rest.split_once(':')?;
+    let text = text.trim();
+    let is_project = project.starts_with(|c: char| c.is_ascii_alphabetic())
+        && !project.contains(char::is_whitespace)
+        && !LEGACY_TAG_KEYS.contains(&project);
+    if !is_project || text.is_empty() || text.starts_with('/') {
+        return None;
+    }
+    let priority = priority.map(|priority| format!("({}) ", priority)).unwrap_or_default();
+    parse_checklist_line(&format!("{}{}{}: {}", checkbox, priority, project, text))
+}
+
+// Tags omado writes itself, which never started a line as a project
+const LEGACY_TAG_KEYS: [&str; 4] = ["due", "rec", "done", "pri"];
+
+fn format_checklist_line(todo: &Todo) -> String {
+    let prefix = if todo.done { "[x]" } else { "[ ]" };
+    let mut line = format!("{} {}", prefix, todo.full_text());
+    if let (true, Some(completed)) = (todo.done, todo.completed) {
+        line.push_str(&format!(" done:{}", completed.format("%Y-%m-%d")));
+    }
+    line
+}
+
+fn split_date(text: &str) -> Option<(NaiveDate, &str)> {
+    let (word, rest) = text.split_once(' ').unwrap_or((text, ""));
+    let date = NaiveDate::parse_from_str(word, "%Y-%m-%d").ok()?;
+    Some((date, rest.trim_start()))
+}
+
+/// Blank lines and `#` comments are not tasks.
+fn parse_todotxt_line(raw: &str) -> Option<Todo> {
+    let line = raw.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+
+    let mut todo = Todo::default();
+    let mut rest = line;
+    if let Some(after) = rest.strip_prefix("x ") {
+        todo.done = true;
+        rest = after.trim_start();
+        if let Some((date, after)) = split_date(rest) {
+            todo.completed = Some(date);
+            rest = after;
+        }
+    } else {
+        let (priority, after) = split_priority(rest);
+        todo.priority = priority;
+        rest = after;
+    }
+    if let Some((date, after)) = split_date(rest) {
+        todo.created = Some(date);
+        rest = after;
+    }
+
+    // The first +project is the task's project; any others stay in the text
+    let mut words = Vec::new();
+    for word in rest.split_whitespace() {
+        match word.strip_prefix('+').filter(|p| !p.is_empty()) {
+            Some(project) if todo.project.is_none() => {
+                todo.project = Some(project.to_string());
+                todo.layout.push(Word::Project);
+            }
+            _ => words.extend(todo.take_word(word)),
+        }
+    }
+    todo.text = words.join(" ");
+
+    // Completed tasks keep their priority as a pri: tag
+    if todo.done {
+        if let Some(pos) = todo.tags.iter().position(|(k, v)| k == "pri" && v.len() == 1) {
+            let (_, value) = todo.remove_tag(pos);
+            todo.priority = value.chars().next().filter(char::is_ascii_uppercase);
+        }
+    }
+    Some(todo)
+}
+
+fn format_todotxt_line(todo: &Todo) -> String {
+    let mut line = String::new();
+    if todo.done {
+        line.push_str("x ");
+        if let Some(completed) = todo.completed {
+            line.push_str(&format!("{} ", completed.format("%Y-%m-%d")));
+        }
+    } else if let Some(priority) = todo.priority {
+        line.push_str(&format!("({}) ", priority));
+    }
+    if let Some(created) = todo.created {
+        line.push_str(&format!("{} ", created.format("%Y-%m-%d")));
+    }
+
+    // A todo.txt +project ends at the first space, and checklist projects
+    // may contain some
+    let project = todo.project.as_deref().map(|project| project.split_whitespace().collect::<Vec<_>>().join("-"));
+    line.push_str(&todo.words(project.as_deref()));
+
+    if let (true, Some(priority)) = (todo.done, todo.priority) {
+        line.push_str(&format!(" pri:{}", priority));
+    }
+    line
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn round_trip(content: &str) {
+        assert_eq!(TodoDocument::parse(content).contents(), content);
+    }
+
+    #[test]
+    fn untouched_files_are_byte_identical() {
+        round_trip("");
+        round_trip("[ ] Buy milk\n[x] work: Fix parser bug\n");
+        round_trip("# Groceries\n[ ] Buy milk\n\n# Work\n[X] work: Ship it\n");
+        round_trip("  [ ] indented task  \n\tjust a note\n");
+        round_trip("[ ] no trailing newline");
+        round_trip("[ ] crlf\r\n// comment\r\n[x] done\r\n");
+        round_trip("\n\n[ ] surrounded by blanks\n\n");
+        round_trip("[ ]missing space\n[y] unknown marker\n");
+    }
+
+    #[test]
+    fn recognizes_uppercase_done_marker() {
+        let doc = TodoDocument::parse("[X] Shout\n");
+        assert_eq!(doc.len(), 1);
+        assert!(doc[0].done);
+    }
+
+    #[test]
+    fn edit_only_rewrites_changed_line() {
+        let mut doc = TodoDocument::parse("# header\n  [ ] first\n[ ] work:   second\n\nfooter");
+        doc[1].done = true;
+        assert_eq!(doc.contents(), "# header\n  [ ] first\n[x] work: second\n\nfooter");
+    }
+
+    #[test]
+    fn push_appends_after_last_line() {
+        let mut doc = TodoDocument::parse("note\n[ ] one");
+        doc.push(Todo::from_input("home: two"));
+        assert_eq!(doc.contents(), "note\n[ ] one\n[ ] home: two\n");
+    }
+
+    #[test]
+    fn new_file_ends_lines() {
+        let mut doc = TodoDocument::default();
+        doc.push(Todo::from_input("one"));
+        doc.push(Todo::from_input("two"));
+        assert_eq!(doc.contents(), "[ ] one\n[ ] two\n");
+    }
+
+    #[test]
+    fn push_uses_file_line_ending() {
+        let mut doc = TodoDocument::parse("[ ] one\r\n");
+        doc.push(Todo::from_input("two"));
+        assert_eq!(doc.contents(), "[ ] one\r\n[ ] two\r\n");
+    }
+
+    #[test]
+    fn insert_restores_removed_task_in_place() {
+        let content = "# a\n[ ] one\n# b\n[ ] two\n[x] three\n";
+        let mut doc = TodoDocument::parse(content);
+        let removed = doc.remove(1);
+        doc.insert(1, removed);
+        assert_eq!(doc.contents(), "# a\n[ ] one\n# b\n[ ] two\n[x] three\n");
+        let removed = doc.remove(2);
+        doc.insert(2, removed);
+        assert_eq!(doc.contents(), content);
+    }
+
+    #[test]
+    fn move_task_keeps_raw_lines_and_layout() {
+        let mut doc = TodoDocument::parse("# a\n[ ]  one\n# b\n[ ] two\n[x] three");
+        doc.move_task(2, 0);
+        assert_eq!(doc.contents(), "# a\n[x] three\n# b\n[ ]  one\n[ ] two\n");
+        doc.move_task(0, 2);
+        assert_eq!(doc[2].text, "three");
+        assert_eq!(doc.contents(), "# a\n[ ]  one\n# b\n[ ] two\n[x] three");
+    }
+
+    #[test]
+    fn completing_recurring_task_appends_next_occurrence() {
+        let mut doc = TodoDocument::parse("[ ] work: report due:2026-10-02 rec:+1w\n");
+        assert_eq!(doc.set_done(0, true), Some(1));
+        assert!(doc[0].done);
+        assert_eq!(
+            doc.contents(),
+            format!(
+                "[x] work: report due:2026-10-02 rec:+1w done:{}\n[ ] work: report due:2026-10-09 rec:+1w\n",
+                today().format("%Y-%m-%d")
+            )
+        );
+        assert_eq!(doc.set_done(1, false), None);
+
+        // Completing it again doesn't add another copy
+        let content = doc.contents();
+        assert_eq!(doc.set_done(0, false), None);
+        assert_eq!(doc.set_done(0, true), None);
+        assert_eq!(doc.contents(), content);
+    }
+
+    #[test]
+    fn rewriting_keeps_contexts_and_tags_in_place() {
+        let mut doc = TodoDocument::parse("[ ] meet @john about foo in room:5 tomorrow\n");
+        doc[0].done = true;
+        assert_eq!(doc.contents(), "[x] meet @john about foo in room:5 tomorrow\n");
+
+        doc[0].tags.push(("due".to_string(), "2026-10-20".to_string()));
+        assert_eq!(doc.contents(), "[x] meet @john about foo in room:5 tomorrow due:2026-10-20\n");
+    }
+
+    #[test]
+    fn checklist_records_completion_date() {
+        let mut doc = TodoDocument::parse("[x] Ship it done:2026-10-02\n[ ] Pay rent\n");
+        assert_eq!(doc[0].completed, NaiveDate::from_ymd_opt(2026, 10, 2));
+        assert_eq!(doc[0].full_text(), "Ship it");
+        assert!(doc[0].tags.is_empty());
+
+        doc[1].done = true;
+        doc[1].completed = NaiveDate::from_ymd_opt(2026, 10, 16);
+        assert_eq!(doc.contents(), "[x] Ship it done:2026-10-02\n[x] Pay rent done:2026-10-16\n");
+        doc[0].done = false;
+        doc[0].completed = None;
+        assert_eq!(doc.contents(), "[ ] Ship it\n[x] Pay rent done:2026-10-16\n");
+
+        doc.set_format(Format::TodoTxt);
+        assert_eq!(doc.contents(), "Ship it\nx 2026-10-16 Pay rent\n");
+
+        // Open tasks keep the tag as it is
+        let mut doc = TodoDocument::parse("[ ] Write done:2026-10-02 report\n");
+        assert_eq!(doc[0].completed, None);
+        doc[0].priority = Some('A');
+        assert_eq!(doc.contents(), "[ ] (A) Write done:2026-10-02 report\n");
+        doc[0].done = true;
+        doc[0].completed = NaiveDate::from_ymd_opt(2026, 10, 16);
+        let doc = TodoDocument::parse(&doc.contents());
+        assert_eq!(doc[0].completed, NaiveDate::from_ymd_opt(2026, 10, 16));
+        assert_eq!(doc[0].full_text(), "(A) Write done:2026-10-02 report");
+    }
+
+    #[test]
+    fn migrates_projects_without_a_space_in_unsaved_checklists() {
+        let content = "[ ] work:fix login\n[x] (A) home:buy milk due:2026-10-20\n[ ] due:2026-10-20 pay rent\n[ ] read https://example.com\n";
+        let doc = TodoDocument::parse(content);
+        assert_eq!(doc[0].project.as_deref(), Some("work"));
+        assert_eq!(doc[0].text, "fix login");
+        assert_eq!(doc[1].project.as_deref(), Some("home"));
+        assert_eq!(doc[1].priority, Some('A'));
+        assert_eq!(doc[2].project, None);
+        assert_eq!(doc[3].project, None);
+        assert_eq!(
+            doc.contents(),
+            "[ ] work: fix login\n[x] (A) home: buy milk due:2026-10-20\n[ ] due:2026-10-20 pay rent\n[ ] read https://example.com\n"
+        );
+
+        // Once omado has saved the file, `work:fix` is a tag
+        let doc = TodoDocument::parse_as("[ ] work:fix login\n", Format::Checklist);
+        assert_eq!(doc[0].project, None);
+        assert_eq!(doc[0].tags, vec![("work".to_string(), "fix".to_string())]);
+    }
+
+    #[test]
+    fn remove_keeps_surrounding_lines() {
+        let mut doc = TodoDocument::parse("# a\n[ ] one\n# b\n[ ] two\n[x] three\n");
+        let removed = doc.remove(1);
+        assert_eq!(removed.text, "two");
+        assert_eq!(doc.contents(), "# a\n[ ] one\n# b\n[x] three\n");
+        doc[1].done = false;
+        assert_eq!(doc.contents(), "# a\n[ ] one\n# b\n[ ] three\n");
+    }
+
+    #[test]
+    fn parses_nested_projects() {
+        let doc = TodoDocument::parse("[ ] work: backend: fix parser\n[ ] work: Call Bob at 10: bring notes\n");
+        assert_eq!((doc[0].project.as_deref(), doc[0].text.as_str()), (Some("work:backend"), "fix parser"));
+        assert_eq!(doc[0].full_text(), "work:backend: fix parser");
+        // Only single words nest
+        assert_eq!(doc[1].project.as_deref(), Some("work"));
+
+        let filter = |name: &str| crate::ProjectFilter::Project(name.to_string());
+        assert!(filter("work").matches(&doc[0]) && filter("work:backend").matches(&doc[0]));
+        assert!(!filter("wor").matches(&doc[0]) && !filter("work:backend").matches(&doc[1]));
+    }
+
+    #[test]
+    fn detects_format() {
+        assert_eq!(TodoDocument::parse("# notes\n[ ] task\n").format(), Format::Checklist);
+        assert_eq!(TodoDocument::parse("(A) Call mom +family\n").format(), Format::TodoTxt);
+        assert_eq!(TodoDocument::parse("# only a comment\n\n").format(), Format::Checklist);
+    }
+
+    #[test]
+    fn notes_only_file_stays_a_checklist() {
+        let mut doc = TodoDocument::parse("Shopping notes\n// my list\n");
+        assert_eq!(doc.format(), Format::Checklist);
+        assert!(doc.is_empty());
+        doc.push(Todo::from_input("milk"));
+        assert_eq!(doc.contents(), "Shopping notes\n// my list\n[ ] milk\n");
+    }
+
+    #[test]
+    fn stray_checklist_line_keeps_todotxt_tasks() {
+        let doc = TodoDocument::parse("(A) Call mom +family\n[ ] pasted from elsewhere\nx 2026-10-01 Pay rent\n");
+        assert_eq!(doc.format(), Format::TodoTxt);
+        assert_eq!(doc.len(), 3);
+        assert_eq!(doc[0].project.as_deref(), Some("family"));
+
+        // A saved format is never second-guessed
+        let doc = TodoDocument::parse_as("[ ] one\n[ ] two\n(A) Call mom +family\n", Format::TodoTxt);
+        assert_eq!(doc[2].priority, Some('A'));
+    }
+
+    #[test]
+    fn todotxt_files_round_trip() {
+        round_trip("(A) 2026-10-01 Call mom +family @phone\nx 2026-10-16 2026-10-01 Pay rent due:2026-10-15\n");
+        round_trip("# comment\n\nBuy   milk  +groceries\n");
+    }
+
+    #[test]
+    fn parses_todotxt_fields() {
+        let doc = TodoDocument::parse(
+            "(B) 2026-10-01 Review +work PR @office due:2026-10-20 +later\nx 2026-10-16 2026-10-02 Ship it pri:A\n",
+        );
+        let open = &doc[0];
+        assert_eq!(open.priority, Some('B'));
+        assert_eq!(open.created, NaiveDate::from_ymd_opt(2026, 10, 1));
+        assert_eq!(open.project.as_deref(), Some("work"));
+        assert_eq!(open.text, "Review PR +later");
+        assert_eq!(open.contexts, vec!["office".to_string()]);
+        assert_eq!(open.tags, vec![("due".to_string(), "2026-10-20".to_string())]);
+
+        let done = &doc[1];
+        assert!(done.done);
+        assert_eq!(done.completed, NaiveDate::from_ymd_opt(2026, 10, 16));
+        assert_eq!(done.created, NaiveDate::from_ymd_opt(2026, 10, 2));
+        assert_eq!(done.priority, Some('A'));
+        assert!(done.tags.is_empty());
+    }
+
+    #[test]
+    fn writes_todotxt_fields() {
+        let mut doc = TodoDocument::parse("(A) 2026-10-01 Review PR +work @office +later due:2026-10-20\n");
+        doc[0].done = true;
+        doc[0].completed = NaiveDate::from_ymd_opt(2026, 10, 16);
+        assert_eq!(
+            doc.contents(),
+            "x 2026-10-16 2026-10-01 Review PR +work @office +later due:2026-10-20 pri:A\n"
+        );
+        let reparsed = TodoDocument::parse(&doc.contents());
+        assert!(reparsed[0] == doc[0]);
+    }
+
+    #[test]
+    fn converts_between_formats() {
+        let mut doc = TodoDocument::parse("# groceries\n[ ] (A) home: Buy milk @store\n[x] Call mom\n");
+        doc.set_format(Format::TodoTxt);
+        assert_eq!(doc.contents(), "# groceries\n(A) Buy milk +home @store\nx Call mom\n");
+        let mut doc = TodoDocument::parse(&doc.contents());
+        assert_eq!(doc.format(), Format::TodoTxt);
+        doc.set_format(Format::Checklist);
+        assert_eq!(doc.contents(), "# groceries\n[ ] (A) home: Buy milk @store\n[x] Call mom\n");
+    }
+
+    #[test]
+    fn converts_projects_with_spaces() {
+        let mut doc = TodoDocument::parse("[ ] My Project: buy milk\n");
+        doc.set_format(Format::TodoTxt);
+        assert_eq!(doc.contents(), "buy milk +My-Project\n");
+        let mut doc = TodoDocument::parse(&doc.contents());
+        assert_eq!(doc[0].project.as_deref(), Some("My-Project"));
+        assert_eq!(doc[0].text, "buy milk");
+        doc.set_format(Format::Checklist);
+        assert_eq!(doc.contents(), "[ ] My-Project: buy milk\n");
+    }
+}
@@ -0,0 +1,322 @@
+use std::collections::{HashMap, HashSet};
+use std::fs::File;
+use std::io::{self, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, Sender};
+
+use eframe::egui;
+
+/// A single face inside a font file (`.ttc` collections hold several).
+#[derive(Clone)]
+pub(crate) struct FontFace {
+    pub path: PathBuf,
+    pub index: u32,
+}
+
+/// Where a family lookup stands.
+pub(crate) enum Lookup {
+    Pending,
+    Found(FontFace),
+    Missing,
+}
+
+/// Resolves family names such as `JetBrainsMono Nerd Font` to font files by
+/// scanning the XDG font directories, similar to what fontconfig does.
+///
+/// Scanning reads every installed font on a first miss, so lookups run on a
+/// background thread that wakes the egui context when done. Results are
+/// cached, including misses until `retry_missing`.
+pub(crate) struct FontResolver {
+    ctx: egui::Context,
+    cache: HashMap<String, Option<FontFace>>,
+    pending: HashSet<String>,
+    sender: Sender<(String, Option<FontFace>)>,
+    receiver: Receiver<(String, Option<FontFace>)>,
+}
+
+impl FontResolver {
+    pub fn new(ctx: egui::Context) -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { ctx, cache: HashMap::new(), pending: HashSet::new(), sender, receiver }
+    }
+
+    pub fn resolve(&mut self, family: &str) -> Lookup {
+        for (key, face) in self.receiver.try_iter() {
+            self.pending.remove(&key);
+            self.cache.insert(key, face);
+        }
+
+        let key = family.to_lowercase();
+        match self.cache.get(&key) {
+            Some(Some(face)) => return Lookup::Found(face.clone()),
+            Some(None) => return Lookup::Missing,
+            None => {}
+        }
+        if self.pending.insert(key.clone()) {
+            let (family, sender, ctx) = (family.to_string(), self.sender.clone(), self.ctx.clone());
+            let thread_key = key.clone();
+            let spawned = std::thread::Builder::new().name("omado-fonts".to_string()).spawn(move || {
+                let _ = sender.send((thread_key, find_family(&family)));
+                ctx.request_repaint();
+            });
+            if spawned.is_err() {
+                self.pending.remove(&key);
+                return Lookup::Missing;
+            }
+        }
+        Lookup::Pending
+    }
+
+    /// Forgets families that weren't found, e.g. because the font was
+    /// installed since. Returns whether there were any.
+    pub fn retry_missing(&mut self) -> bool {
+        let before = self.cache.len();
+        self.cache.retain(|_, face| face.is_some());
+        self.cache.len() != before
+    }
+}
+
+fn font_dirs() -> Vec<PathBuf> {
+    let home = std::env::var("HOME").ok().map(PathBuf::from);
+    let mut dirs = Vec::new();
+
+    match std::env::var("XDG_DATA_HOME") {
+        Ok(data_home) if !data_home.is_empty() => dirs.push(PathBuf::from(data_home).join("fonts")),
+        _ => dirs.extend(home.as_ref().map(|h| h.join(".local/share/fonts"))),
+    }
+    dirs.extend(home.as_ref().map(|h| h.join(".fonts")));
+
+    let data_dirs = std::env::var("XDG_DATA_DIRS")
+        .ok()
+        .filter(|d| !d.is_empty())
+        .unwrap_or_else(|| "/usr/local/share:/usr/share".to_string());
+    dirs.extend(data_dirs.split(':').filter(|d| !d.is_empty()).map(|d| PathBuf::from(d).join("fonts")));
+    dirs
+}
+
+fn collect_font_files(dir: &Path, depth: usize, out: &mut Vec<PathBuf>) {
+    let Ok(entries) = std::fs::read_dir(dir) else { return };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            // Bounded to survive symlink loops
+            if depth < 8 {
+                collect_font_files(&path, depth + 1, out);
+            }
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| matches!(e.to_ascii_lowercase().as_str(), "ttf" | "otf" | "ttc"))
+        {
+            out.push(path);
+        }
+    }
+}
+
+/// Lowercase alphanumerics only, so `JetBrains Mono` matches `JetBrainsMono-Regular.ttf`.
+fn normalize(name: &str) -> String {
+    name.chars().filter(|c| c.is_alphanumeric()).flat_map(char::to_lowercase).collect()
+}
+
+fn find_family(family: &str) -> Option<FontFace> {
+    let mut files = Vec::new();
+    for dir in font_dirs() {
+        collect_font_files(&dir, 0, &mut files);
+    }
+
+    // Reading metadata is the slow part, so try files whose name looks
+    // right before falling back to every installed font.
+    let wanted = normalize(family);
+    let (likely, rest): (Vec<_>, Vec<_>) = files.into_iter().partition(|path| {
+        path.file_stem()
+            .and_then(|s| s.to_str())
+            .is_some_and(|stem| normalize(stem).starts_with(&wanted))
+    });
+
+    best_match(family, &likely).or_else(|| best_match(family, &rest))
+}
+
+fn best_match(family: &str, files: &[PathBuf]) -> Option<FontFace> {
+    let mut best: Option<(u8, FontFace)> = None;
+    for path in files {
+        let Ok(faces) = read_face_names(path) else { continue };
+        for (index, names) in faces.into_iter().enumerate() {
+            if !names.families.iter().any(|f| f.eq_ignore_ascii_case(family)) {
+                continue;
+            }
+            let rank = style_rank(&names.styles);
+            let better = match &best {
+                Some((best_rank, _)) => rank < *best_rank,
+                None => true,
+            };
+            if better {
+                best = Some((rank, FontFace { path: path.clone(), index: index as u32 }));
+                if rank == 0 {
+                    return best.map(|(_, face)| face);
+                }
+            }
+        }
+    }
+    best.map(|(_, face)| face)
+}
+
+/// Lower is better: prefer the upright regular weight of a family.
+fn style_rank(styles: &[String]) -> u8 {
+    let is = |style: &str| styles.iter().any(|s| s.eq_ignore_ascii_case(style));
+    if is("Regular") {
+        0
+    } else if is("Book") || is("Normal") || is("Roman") {
+        1
+    } else if styles.iter().any(|s| {
+        let s = s.to_lowercase();
+        s.contains("italic") || s.contains("oblique")
+    }) {
+        3
+    } else {
+        2
+    }
+}
+
+#[derive(Default)]
+struct FaceNames {
+    families: Vec<String>,
+    styles: Vec<String>,
+}
+
+fn read_u16(buf: &[u8], at: usize) -> Option<u16> {
+    buf.get(at..at + 2).map(|b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn read_u32(buf: &[u8], at: usize) -> Option<u32> {
+    buf.get(at..at + 4).map(|b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_at(file: &mut File, offset: u64, len: usize) -> io::Result<Vec<u8>> {
+    let mut buf = vec![0; len];
+    file.seek(SeekFrom::Start(offset))?;
+    file.read_exact(&mut buf)?;
+    Ok(buf)
+}
+
+fn invalid() -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, "malformed font")
+}
+
+/// Reads family and style names of every face in a TrueType/OpenType file
+/// (or collection) without loading the whole file.
+fn read_face_names(path: &Path) -> io::Result<Vec<FaceNames>> {
+    let mut file = File::open(path)?;
+    let header = read_at(&mut file, 0, 12)?;
+
+    let offsets = if &header[0..4] == b"ttcf" {
+        let count = read_u32(&header, 8).ok_or_else(invalid)? as usize;
+        let table = read_at(&mut file, 12, count.min(256) * 4)?;
+        (0..count.min(256)).filter_map(|i| read_u32(&table, i * 4)).collect()
+    } else {
+        vec![0]
+    };
+
+    offsets.into_iter().map(|offset| read_names(&mut file, offset as u64)).collect()
+}
+
+fn read_names(file: &mut File, offset: u64) -> io::Result<FaceNames> {
+    let header = read_at(file, offset, 12)?;
+    let num_tables = read_u16(&header, 4).ok_or_else(invalid)? as usize;
+    let records = read_at(file, offset + 12, num_tables * 16)?;
+
+    let name_table = (0..num_tables)
+        .map(|i| i * 16)
+        .find(|&at| &records[at..at + 4] == b"name")
+        .and_then(|at| Some((read_u32(&records, at + 8)?, read_u32(&records, at + 12)?)));
+    let Some((table_offset, table_len)) = name_table else { return Err(invalid()) };
+    // Real name tables are a few KB; don't trust a corrupt length
+    let table = read_at(file, table_offset as u64, (table_len as usize).min(1 << 20))?;
+
+    let count = read_u16(&table, 2).ok_or_else(invalid)? as usize;
+    let storage = read_u16(&table, 4).ok_or_else(invalid)? as usize;
+    let mut names = FaceNames::default();
+    let mut legacy_styles = Vec::new();
+    for i in 0..count {
+        let at = 6 + i * 12;
+        let (Some(platform), Some(name_id), Some(len), Some(str_offset)) = (
+            read_u16(&table, at),
+            read_u16(&table, at + 6),
+            read_u16(&table, at + 8),
+            read_u16(&table, at + 10),
+        ) else {
+            break;
+        };
+
+        // 1/2: legacy family/style, 16/17: typographic family/style
+        let list = match name_id {
+            1 | 16 => &mut names.families,
+            2 => &mut legacy_styles,
+            17 => &mut names.styles,
+            _ => continue,
+        };
+        let start = storage + str_offset as usize;
+        let Some(bytes) = table.get(start..start + len as usize) else { continue };
+        let value = match platform {
+            // Unicode and Windows platforms store UTF-16BE
+            0 | 3 => {
+                let units: Vec<u16> = bytes.chunks_exact(2).map(|c| u16::from_be_bytes([c[0], c[1]])).collect();
+                String::from_utf16_lossy(&units)
+            }
+            _ => bytes.iter().map(|&b| b as char).collect(),
+        };
+        if !list.contains(&value) {
+            list.push(value);
+        }
+    }
+    // A `Medium` face is legacy family `X Medium`, style `Regular`, so the
+    // typographic style is the one to rank by
+    if names.styles.is_empty() {
+        names.styles = legacy_styles;
+    }
+    Ok(names)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture(name: &str) -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/fonts").join(name)
+    }
+
+    #[test]
+    fn reads_names_of_fonts_and_collections() {
+        let faces = read_face_names(&fixture("TinySans-Regular.ttf")).unwrap();
+        assert_eq!(faces.len(), 1);
+        // The Windows and Macintosh records are the same name
+        assert_eq!((faces[0].families.clone(), faces[0].styles.clone()), (vec!["Tiny Sans".to_string()], vec!["Regular".to_string()]));
+
+        let faces = read_face_names(&fixture("TinySans.ttc")).unwrap();
+        assert_eq!(faces.len(), 2);
+        assert_eq!(faces[0].styles, ["Italic"]);
+        assert_eq!(faces[1].families, ["Tiny Sans Medium", "Tiny Sans"]);
+        assert_eq!(faces[1].styles, ["Medium"]);
+
+        assert!(read_face_names(Path::new(file!())).is_err());
+    }
+
+    #[test]
+    fn prefers_the_upright_regular_face() {
+        assert_eq!(normalize("JetBrains Mono"), normalize("JetBrainsMono"));
+        assert_eq!(normalize("Tiny-Sans_2"), "tinysans2");
+
+        let styles = |names: &[&str]| names.iter().map(|name| name.to_string()).collect::<Vec<_>>();
+        assert_eq!(style_rank(&styles(&["Regular"])), 0);
+        assert_eq!(style_rank(&styles(&["Book"])), 1);
+        assert_eq!(style_rank(&styles(&["Bold"])), 2);
+        assert_eq!(style_rank(&styles(&["Bold Italic"])), 3);
+
+        // The collection only has an italic and a medium face of Tiny Sans
+        let files = [fixture("TinySans.ttc"), fixture("TinySans-Regular.ttf")];
+        let face = best_match("tiny sans", &files).unwrap();
+        assert!(face.path.ends_with("TinySans-Regular.ttf"));
+        let face = best_match("Tiny Sans", &files[..1]).unwrap();
+        assert_eq!(face.index, 1);
+        assert!(best_match("Other Sans", &files).is_none());
+    }
+}
@@ -0,0 +1,220 @@
+use std::collections::VecDeque;
+
+use crate::document::TodoDocument;
+use crate::Todo;
+
+// Oldest changes are forgotten beyond this
+const MAX_HISTORY: usize = 100;
+
+/// A single reversible modification of the task list, by task index.
+#[derive(Clone)]
+pub(crate) enum Edit {
+    Insert { idx: usize, todo: Todo },
+    Remove { idx: usize, todo: Todo },
+    Replace { idx: usize, before: Todo, after: Todo },
+    Move { from: usize, to: usize },
+    // The pinned projects, which follow projects that are renamed or deleted.
+    // Saved on their own, so not part of the todo file.
+    Pins { before: Vec<String>, after: Vec<String> },
+}
+
+impl Edit {
+    fn inverse(&self) -> Edit {
+        match self.clone() {
+            Edit::Insert { idx, todo } => Edit::Remove { idx, todo },
+            Edit::Remove { idx, todo } => Edit::Insert { idx, todo },
+            Edit::Replace { idx, before, after } => Edit::Replace { idx, before: after, after: before },
+            Edit::Move { from, to } => Edit::Move { from: to, to: from },
+            Edit::Pins { before, after } => Edit::Pins { before: after, after: before },
+        }
+    }
+
+    fn apply(self, todos: &mut TodoDocument, pins: &mut Vec<String>) {
+        match self {
+            Edit::Insert { idx, todo } => todos.insert(idx, todo),
+            Edit::Remove { idx, .. } => {
+                todos.remove(idx);
+            }
+            Edit::Replace { idx, after, .. } => todos[idx] = after,
+            Edit::Move { from, to } => todos.move_task(from, to),
+            Edit::Pins { after, .. } => *pins = after,
+        }
+    }
+
+    /// The task the user will want selected after this edit is applied.
+    fn focus(&self) -> Option<usize> {
+        match *self {
+            Edit::Insert { idx, .. } | Edit::Replace { idx, .. } | Edit::Move { to: idx, .. } => Some(idx),
+            Edit::Remove { .. } | Edit::Pins { .. } => None,
+        }
+    }
+}
+
+/// Finds `todo` again in a list that may have changed, preferring position `hint`.
+pub(crate) fn locate(todos: &[Todo], hint: usize, todo: &Todo) -> Option<usize> {
+    if todos.get(hint) == Some(todo) {
+        Some(hint)
+    } else {
+        todos.iter().position(|t| t == todo)
+    }
+}
+
+/// Reapplies `edits`, made on top of `base`, to `onto`, a version of the file
+/// someone else has changed since. Tasks are found again by content, and an
+/// edit whose task was changed or removed meanwhile is dropped. Returns the
+/// edits as applied to `onto` and whether none had to be dropped. Pins are
+/// already saved and left out.
+pub(crate) fn rebase(edits: &[Edit], base: &TodoDocument, onto: &mut TodoDocument) -> (Vec<Edit>, bool) {
+    let mut base = base.clone();
+    let mut applied = Vec::new();
+    let mut complete = true;
+    let mut pins = Vec::new();
+    for edit in edits {
+        let rebased = match edit.clone() {
+            Edit::Pins { .. } => continue,
+            Edit::Insert { idx, todo } => Some(Edit::Insert { idx: idx.min(onto.len()), todo }),
+            Edit::Remove { idx, todo } => locate(onto, idx, &todo).map(|idx| Edit::Remove { idx, todo }),
+            Edit::Replace { idx, before, after } => {
+                locate(onto, idx, &before).map(|idx| Edit::Replace { idx, before, after })
+            }
+            Edit::Move { from, to } => locate(onto, from, &base[from])
+                .map(|from| Edit::Move { from, to: to.min(onto.len() - 1) }),
+        };
+        edit.clone().apply(&mut base, &mut pins);
+        match rebased {
+            Some(rebased) => {
+                rebased.clone().apply(onto, &mut pins);
+                applied.push(rebased);
+            }
+            None => complete = false,
+        }
+    }
+    (applied, complete)
+}
+
+/// Undo/redo stacks of GUI changes. Each entry is everything one user action
+/// did, so a bulk operation is undone in one step.
+///
+/// Edits refer to tasks by index, so the history is only valid for the file
+/// it was recorded against and must be cleared when something else changes it.
+#[derive(Default)]
+pub(crate) struct History {
+    undo: VecDeque<Vec<Edit>>,
+    redo: Vec<Vec<Edit>>,
+}
+
+impl History {
+    pub fn record(&mut self, edits: Vec<Edit>) {
+        if edits.is_empty() {
+            return;
+        }
+        self.push_undo(edits);
+        self.redo.clear();
+    }
+
+    fn push_undo(&mut self, edits: Vec<Edit>) {
+        if self.undo.len() == MAX_HISTORY {
+            self.undo.pop_front();
+        }
+        self.undo.push_back(edits);
+    }
+
+    pub fn clear(&mut self) {
+        self.undo.clear();
+        self.redo.clear();
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+
+    /// Reverts the latest change and returns the task to select, if any.
+    /// The edits that reverted it are added to `applied`.
+    pub fn undo(&mut self, todos: &mut TodoDocument, pins: &mut Vec<String>, applied: &mut Vec<Edit>) -> Option<usize> {
+        let edits = self.undo.pop_back()?;
+        let mut focus = None;
+        for edit in edits.iter().rev() {
+            let inverse = edit.inverse();
+            focus = inverse.focus().or(focus);
+            applied.push(inverse.clone());
+            inverse.apply(todos, pins);
+        }
+        self.redo.push(edits);
+        focus
+    }
+
+    /// Reapplies the latest undone change and returns the task to select, if
+    /// any. Its edits are added to `applied`.
+    pub fn redo(&mut self, todos: &mut TodoDocument, pins: &mut Vec<String>, applied: &mut Vec<Edit>) -> Option<usize> {
+        let edits = self.redo.pop()?;
+        let mut focus = None;
+        for edit in &edits {
+            focus = edit.focus().or(focus);
+            applied.push(edit.clone());
+            edit.clone().apply(todos, pins);
+        }
+        self.push_undo(edits);
+        focus
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn undo_and_redo_replay_a_change_in_order() {
+        let mut doc = TodoDocument::parse("[ ] one\n[ ] two\n");
+        let mut history = History::default();
+
+        let before = doc[0].clone();
+        doc[0].set_done(true);
+        let removed = doc.remove(1);
+        let mut pins = vec!["home".to_string()];
+        history.record(vec![
+            Edit::Replace { idx: 0, before, after: doc[0].clone() },
+            Edit::Remove { idx: 1, todo: removed },
+            Edit::Pins { before: vec!["work".to_string()], after: pins.clone() },
+        ]);
+
+        let mut applied = Vec::new();
+        assert_eq!(history.undo(&mut doc, &mut pins, &mut applied), Some(0));
+        assert_eq!(doc.contents(), "[ ] one\n[ ] two\n");
+        assert_eq!(pins, ["work"]);
+        assert!(!history.can_undo());
+
+        history.redo(&mut doc, &mut pins, &mut applied);
+        assert_eq!(applied.len(), 6);
+        assert_eq!(doc.contents(), format!("[x] one done:{}\n", crate::today().format("%Y-%m-%d")));
+        assert_eq!(pins, ["home"]);
+        assert!(history.can_undo() && !history.can_redo());
+    }
+
+    #[test]
+    fn rebase_finds_tasks_again_by_content() {
+        let base = TodoDocument::parse("[ ] one\n[ ] two\n[ ] three\n");
+        let mut ours = base.clone();
+        let mut edits = Vec::new();
+        let before = ours[1].clone();
+        ours[1].set_done(true);
+        edits.push(Edit::Replace { idx: 1, before, after: ours[1].clone() });
+        ours.move_task(2, 0);
+        edits.push(Edit::Move { from: 2, to: 0 });
+        let removed = ours.remove(1);
+        edits.push(Edit::Remove { idx: 1, todo: removed });
+
+        // Meanwhile someone added a task at the top and edited "one"
+        let mut theirs = TodoDocument::parse("[ ] zero\n[ ] one!\n[ ] two\n[ ] three\n");
+        let (applied, complete) = rebase(&edits, &base, &mut theirs);
+        assert!(!complete, "\"one\" was changed elsewhere");
+        assert_eq!(applied.len(), 2);
+        assert_eq!(
+            theirs.contents(),
+            format!("[ ] three\n[ ] zero\n[ ] one!\n[x] two done:{}\n", crate::today().format("%Y-%m-%d"))
+        );
+    }
+}
@@ -0,0 +1,378 @@
+use std::collections::BTreeMap;
+
+use eframe::egui::{Key, Modifiers};
+
+use crate::KeyAction;
+
+/// Names used for actions in the `[keys]` table of the config file.
+const ACTIONS: &[(&str, KeyAction)] = &[
+    ("move_down", KeyAction::MoveDown),
+    ("move_up", KeyAction::MoveUp),
+    ("go_to_top", KeyAction::GoToTop),
+    ("go_to_bottom", KeyAction::GoToBottom),
+    ("edit", KeyAction::EditSelected),
+    ("add", KeyAction::AddNew),
+    ("toggle", KeyAction::ToggleSelected),
+    ("delete", KeyAction::Delete),
+    ("repeat", KeyAction::Repeat),
+    ("cycle_filter", KeyAction::CycleFilter),
+    ("cycle_project", KeyAction::CycleProject),
+    ("clear_filters", KeyAction::ClearAllFilters),
+    ("project_palette", KeyAction::OpenProjectPalette),
+    ("list_palette", KeyAction::OpenListPalette),
+    ("search", KeyAction::ToggleSearch),
+    ("cancel", KeyAction::ClearSearch),
+    ("font_bigger", KeyAction::IncreaseFontSize),
+    ("font_smaller", KeyAction::DecreaseFontSize),
+    ("font_reset", KeyAction::ResetFontSize),
+    ("retry", KeyAction::RetryStorage),
+    ("priority_up", KeyAction::RaisePriority),
+    ("priority_down", KeyAction::LowerPriority),
+    ("priority_a", KeyAction::SetPriority(Some('A'))),
+    ("priority_b", KeyAction::SetPriority(Some('B'))),
+    ("priority_c", KeyAction::SetPriority(Some('C'))),
+    ("priority_none", KeyAction::SetPriority(None)),
+    ("undo", KeyAction::Undo),
+    ("redo", KeyAction::Redo),
+    ("move_task_down", KeyAction::MoveTaskDown),
+    ("move_task_up", KeyAction::MoveTaskUp),
+    ("visual", KeyAction::ToggleVisual),
+    ("move_to_project", KeyAction::MoveToProject),
+    ("yank", KeyAction::Yank),
+    ("archive", KeyAction::Archive),
+    ("restore", KeyAction::Restore),
+    ("priority_sort", KeyAction::TogglePrioritySort),
+];
+
+const DEFAULT_BINDINGS: &[(&str, KeyAction)] = &[
+    ("j", KeyAction::MoveDown),
+    ("Down", KeyAction::MoveDown),
+    ("k", KeyAction::MoveUp),
+    ("Up", KeyAction::MoveUp),
+    ("g", KeyAction::GoToTop),
+    ("G", KeyAction::GoToBottom),
+    ("J", KeyAction::MoveTaskDown),
+    ("Shift+Down", KeyAction::MoveTaskDown),
+    ("Alt+j", KeyAction::MoveTaskDown),
+    ("Alt+Down", KeyAction::MoveTaskDown),
+    ("K", KeyAction::MoveTaskUp),
+    ("Shift+Up", KeyAction::MoveTaskUp),
+    ("Alt+k", KeyAction::MoveTaskUp),
+    ("Alt+Up", KeyAction::MoveTaskUp),
+    ("Enter", KeyAction::EditSelected),
+    ("a", KeyAction::AddNew),
+    ("x", KeyAction::ToggleSelected),
+    ("d", KeyAction::Delete),
+    (".", KeyAction::Repeat),
+    ("u", KeyAction::Undo),
+    ("Ctrl+r", KeyAction::Redo),
+    ("v", KeyAction::ToggleVisual),
+    ("V", KeyAction::ToggleVisual),
+    ("m", KeyAction::MoveToProject),
+    ("y", KeyAction::Yank),
+    ("A", KeyAction::Archive),
+    ("r", KeyAction::Restore),
+    ("f", KeyAction::CycleFilter),
+    ("p", KeyAction::CycleProject),
+    ("P", KeyAction::OpenProjectPalette),
+    ("L", KeyAction::OpenListPalette),
+    ("S", KeyAction::ToggleSearch),
+    ("c", KeyAction::ClearAllFilters),
+    ("s", KeyAction::TogglePrioritySort),
+    ("+", KeyAction::RaisePriority),
+    ("=", KeyAction::RaisePriority),
+    ("-", KeyAction::LowerPriority),
+    ("Alt+1", KeyAction::SetPriority(Some('A'))),
+    ("Alt+2", KeyAction::SetPriority(Some('B'))),
+    ("Alt+3", KeyAction::SetPriority(Some('C'))),
+    ("Alt+0", KeyAction::SetPriority(None)),
+    ("Ctrl++", KeyAction::IncreaseFontSize),
+    ("Ctrl+=", KeyAction::IncreaseFontSize),
+    ("Ctrl+-", KeyAction::DecreaseFontSize),
+    ("Ctrl+0", KeyAction::ResetFontSize),
+    ("Ctrl+s", KeyAction::RetryStorage),
+    ("Escape", KeyAction::ClearSearch),
+];
+
+/// Help line entries: actions sharing a description are shown as `j/k: Move`.
+const NORMAL_HELP: &[(&[KeyAction], &str)] = &[
+    (&[KeyAction::MoveDown, KeyAction::MoveUp], "Move"),
+    (&[KeyAction::GoToTop, KeyAction::GoToBottom], "Top/Bottom"),
+    (&[KeyAction::EditSelected], "Edit"),
+    (&[KeyAction::AddNew], "Add"),
+    (&[KeyAction::ToggleSelected], "Toggle"),
+    (&[KeyAction::Delete], "Delete"),
+    (&[KeyAction::Repeat], "Repeat"),
+    (&[KeyAction::CycleFilter], "Filter"),
+    (&[KeyAction::CycleProject], "Project"),
+    (&[KeyAction::ToggleSearch], "Search"),
+    (&[KeyAction::OpenProjectPalette], "Projects"),
+    (&[KeyAction::OpenListPalette], "Lists"),
+    (&[KeyAction::RaisePriority, KeyAction::LowerPriority], "Priority"),
+    (&[KeyAction::ToggleVisual], "Visual"),
+    (&[KeyAction::MoveTaskDown, KeyAction::MoveTaskUp], "Move Task"),
+    (&[KeyAction::TogglePrioritySort], "Sort"),
+    (&[KeyAction::Undo, KeyAction::Redo], "Undo/Redo"),
+    (&[KeyAction::Archive], "Archive"),
+    (&[KeyAction::ClearAllFilters], "Clear Filters"),
+    (&[KeyAction::IncreaseFontSize, KeyAction::DecreaseFontSize], "Font Size"),
+];
+
+const VISUAL_HELP: &[(&[KeyAction], &str)] = &[
+    (&[KeyAction::MoveDown, KeyAction::MoveUp], "Extend"),
+    (&[KeyAction::ToggleSelected], "Toggle"),
+    (&[KeyAction::Delete], "Delete"),
+    (&[KeyAction::MoveToProject], "Move to Project"),
+    (&[KeyAction::RaisePriority, KeyAction::LowerPriority], "Priority"),
+    (&[KeyAction::Yank], "Yank"),
+    (&[KeyAction::ClearSearch], "Cancel"),
+];
+
+// The archive view can only navigate and restore
+const ARCHIVE_HELP: &[(&[KeyAction], &str)] = &[
+    (&[KeyAction::MoveDown, KeyAction::MoveUp], "Move"),
+    (&[KeyAction::GoToTop, KeyAction::GoToBottom], "Top/Bottom"),
+    (&[KeyAction::Restore], "Restore"),
+    (&[KeyAction::ToggleVisual], "Visual"),
+    (&[KeyAction::Yank], "Yank"),
+    (&[KeyAction::CycleFilter], "Filter"),
+    (&[KeyAction::ToggleSearch], "Search"),
+];
+
+/// Which actions the help line describes.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum HelpMode {
+    Normal,
+    Visual,
+    Archive,
+}
+
+/// A key with modifiers, e.g. `Ctrl+r` or `J` (which is `Shift+j`).
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) struct Chord {
+    key: Key,
+    ctrl: bool,
+    alt: bool,
+    shift: bool,
+}
+
+impl Chord {
+    pub fn parse(text: &str) -> Option<Self> {
+        // `+` separates modifiers but is also a key: `+`, `Ctrl++`
+        let (modifiers, name) = if text == "+" {
+            ("", "+")
+        } else if let Some(modifiers) = text.strip_suffix("++") {
+            (modifiers, "+")
+        } else {
+            text.rsplit_once('+').unwrap_or(("", text))
+        };
+
+        let mut chord = Chord { key: Key::Space, ctrl: false, alt: false, shift: false };
+        for modifier in modifiers.split('+').filter(|m| !m.is_empty()) {
+            match modifier.to_lowercase().as_str() {
+                "ctrl" | "control" => chord.ctrl = true,
+                "alt" => chord.alt = true,
+                "shift" => chord.shift = true,
+                _ => return None,
+            }
+        }
+
+        // On its own, a capital letter means Shift, like in vim
+        if modifiers.is_empty() && name.len() == 1 && name.chars().all(|c| c.is_ascii_uppercase()) {
+            chord.shift = true;
+        }
+        let mut capitalized = name.to_lowercase();
+        if let Some(first) = capitalized.get_mut(0..1) {
+            first.make_ascii_uppercase();
+        }
+        chord.key = Key::from_name(name).or_else(|| Key::from_name(&capitalized))?;
+        if !uses_shift(chord.key) {
+            chord.shift = false;
+        }
+        Some(chord)
+    }
+
+    fn matches(&self, key: Key, modifiers: Modifiers) -> bool {
+        self.key == key
+            && self.ctrl == modifiers.ctrl
+            && self.alt == modifiers.alt
+            && self.shift == (modifiers.shift && uses_shift(key))
+    }
+
+    fn label(&self) -> String {
+        let mut label = String::new();
+        for (held, name) in [(self.ctrl, "Ctrl+"), (self.alt, "Alt+"), (self.shift, "Shift+")] {
+            if held {
+                label.push_str(name);
+            }
+        }
+        let name = self.key.symbol_or_name();
+        if name.len() == 1 && label.is_empty() {
+            label.push_str(&name.to_lowercase());
+        } else {
+            label.push_str(name);
+        }
+        label
+    }
+}
+
+/// Shift is how `+`, `?` and friends are typed on many layouts, so it is
+/// ignored for punctuation and only distinguishes letters and named keys.
+fn uses_shift(key: Key) -> bool {
+    !matches!(
+        key,
+        Key::Colon
+            | Key::Comma
+            | Key::Minus
+            | Key::Period
+            | Key::Plus
+            | Key::Equals
+            | Key::Semicolon
+            | Key::Backslash
+            | Key::Slash
+            | Key::Pipe
+            | Key::Questionmark
+            | Key::OpenBracket
+            | Key::CloseBracket
+            | Key::Backtick
+            | Key::Quote
+    )
+}
+
+/// Which action each key chord triggers outside of text editing.
+pub(crate) struct Keymap {
+    bindings: Vec<(Chord, KeyAction)>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = DEFAULT_BINDINGS
+            .iter()
+            .map(|(chord, action)| (Chord::parse(chord).expect("valid default chord"), *action))
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl Keymap {
+    /// The default keymap with the `[keys]` table of the config file applied.
+    /// Problems with entries are returned as messages; those entries are skipped.
+    pub fn with_overrides(overrides: &BTreeMap<String, String>) -> (Self, Vec<String>) {
+        let mut keymap = Keymap::default();
+        let mut problems = Vec::new();
+        let mut seen: Vec<(Chord, &str)> = Vec::new();
+
+        for (text, name) in overrides {
+            let Some(chord) = Chord::parse(text) else {
+                problems.push(format!("unknown key \"{}\"", text));
+                continue;
+            };
+            let action = if name == "none" {
+                None
+            } else if let Some((_, action)) = ACTIONS.iter().find(|(n, _)| n == name) {
+                Some(*action)
+            } else {
+                problems.push(format!("unknown action \"{}\" for \"{}\"", name, text));
+                continue;
+            };
+            if !chord.ctrl && !chord.alt && chord.key.name().parse::<u32>().is_ok() {
+                problems.push(format!("\"{}\" is reserved for counts", text));
+                continue;
+            }
+            if let Some((_, other)) = seen.iter().find(|(c, _)| *c == chord) {
+                problems.push(format!("\"{}\" and \"{}\" are the same key", other, text));
+                continue;
+            }
+            seen.push((chord, text));
+
+            keymap.bindings.retain(|(c, _)| *c != chord);
+            if let Some(action) = action {
+                keymap.bindings.push((chord, action));
+            }
+        }
+        (keymap, problems)
+    }
+
+    pub fn action(&self, key: Key, modifiers: Modifiers) -> Option<KeyAction> {
+        self.bindings
+            .iter()
+            .find(|(chord, _)| chord.matches(key, modifiers))
+            .map(|(_, action)| *action)
+    }
+
+    /// Label of the first key bound to `action`, e.g. `Ctrl+s`.
+    pub fn shortcut(&self, action: KeyAction) -> Option<String> {
+        self.bindings.iter().find(|(_, a)| *a == action).map(|(chord, _)| chord.label())
+    }
+
+    /// Label of the first key bound to `action`, doubled for actions that
+    /// are typed twice outside of visual mode (`gg`, `dd`).
+    fn key_label(&self, action: KeyAction, visual: bool) -> Option<String> {
+        let label = self.shortcut(action)?;
+        let doubled = !visual && matches!(action, KeyAction::GoToTop | KeyAction::Delete);
+        Some(if doubled { label.repeat(2) } else { label })
+    }
+
+    /// The help line for the current bindings, e.g. `j/k: Move | a: Add`.
+    pub fn help(&self, mode: HelpMode) -> String {
+        let (entries, visual) = match mode {
+            HelpMode::Normal => (NORMAL_HELP, false),
+            HelpMode::Visual => (VISUAL_HELP, true),
+            HelpMode::Archive => (ARCHIVE_HELP, false),
+        };
+        entries
+            .iter()
+            .filter_map(|(actions, description)| {
+                let keys: Vec<String> = actions.iter().filter_map(|a| self.key_label(*a, visual)).collect();
+                (!keys.is_empty()).then(|| format!("{}: {}", keys.join("/"), description))
+            })
+            .collect::<Vec<_>>()
+            .join(" | ")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn overrides(entries: &[(&str, &str)]) -> BTreeMap<String, String> {
+        entries.iter().map(|(k, v)| (k.to_string(), v.to_string())).collect()
+    }
+
+    #[test]
+    fn parses_chords() {
+        let chord = |text| Chord::parse(text).map(|c| (c.key, c.ctrl, c.alt, c.shift));
+        assert_eq!(chord("J"), Some((Key::J, false, false, true)));
+        assert_eq!(chord("shift+j"), chord("J"));
+        assert_eq!(chord("Ctrl+R"), Some((Key::R, true, false, false)));
+        assert_eq!(chord("ctrl+shift+r"), Some((Key::R, true, false, true)));
+        assert_eq!(chord("Ctrl++"), Some((Key::Plus, true, false, false)));
+        assert_eq!(chord("down"), Some((Key::ArrowDown, false, false, false)));
+        assert_eq!(chord("Hyper+x"), None);
+    }
+
+    #[test]
+    fn applies_overrides_and_reports_problems() {
+        let (keymap, problems) = Keymap::with_overrides(&overrides(&[
+            ("t", "toggle"),
+            ("x", "none"),
+            ("shift+q", "undo"),
+            ("Q", "redo"),
+            ("z", "explode"),
+            ("5", "undo"),
+        ]));
+        assert_eq!(keymap.action(Key::T, Modifiers::NONE), Some(KeyAction::ToggleSelected));
+        assert_eq!(keymap.action(Key::X, Modifiers::NONE), None);
+        assert_eq!(keymap.action(Key::Q, Modifiers::SHIFT), Some(KeyAction::Redo));
+        assert_eq!(
+            problems,
+            [
+                "\"5\" is reserved for counts",
+                "\"Q\" and \"shift+q\" are the same key",
+                "unknown action \"explode\" for \"z\"",
+            ]
+        );
+        assert!(keymap.help(HelpMode::Normal).starts_with("j/k: Move | gg/Shift+G: Top/Bottom | Enter: Edit"));
+        assert!(keymap.help(HelpMode::Normal).contains("t: Toggle"));
+    }
+}
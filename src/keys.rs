@@ -0,0 +1,173 @@
+use crate::KeyAction;
+
+// Counts beyond this are typos, not list positions
+const MAX_COUNT: usize = 9999;
+
+/// Where a motion moves the cursor, or which tasks an operator applies to.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum Motion {
+    Down,
+    Up,
+    Top,
+    Bottom,
+    // A doubled operator (`dd`): the current task and `count - 1` below it
+    Lines,
+}
+
+impl Motion {
+    pub fn of(action: KeyAction) -> Option<Self> {
+        match action {
+            KeyAction::MoveDown => Some(Motion::Down),
+            KeyAction::MoveUp => Some(Motion::Up),
+            KeyAction::GoToTop => Some(Motion::Top),
+            KeyAction::GoToBottom => Some(Motion::Bottom),
+            _ => None,
+        }
+    }
+}
+
+/// A complete keystroke sequence such as `5j`, `3dd` or `dG`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Command {
+    pub action: KeyAction,
+    pub count: Option<usize>,
+    // Only set for operators
+    pub motion: Option<Motion>,
+}
+
+impl Command {
+    pub fn new(action: KeyAction) -> Self {
+        Self { action, count: None, motion: None }
+    }
+}
+
+/// Turns key presses, already mapped to actions, into commands following
+/// vim's `[count] operator [count] motion` grammar. `Delete` is the only
+/// operator and `GoToTop` has to be typed twice (`gg`).
+#[derive(Default)]
+pub(crate) struct KeyParser {
+    count: Option<usize>,
+    // Operator waiting for its motion, with the count typed before it
+    operator: Option<(KeyAction, Option<usize>)>,
+    // First `g` of `gg`
+    prefix: bool,
+    // Keys typed so far, for the status line
+    typed: String,
+}
+
+impl KeyParser {
+    /// Keys of the unfinished command, e.g. `3d`.
+    pub fn pending(&self) -> &str {
+        &self.typed
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Adds a digit to the count. Returns false for a `0` that can't start a
+    /// count, which is then free to be used otherwise.
+    pub fn digit(&mut self, digit: u32) -> bool {
+        if digit == 0 && self.count.is_none() {
+            return false;
+        }
+        let count = self.count.unwrap_or(0).saturating_mul(10).saturating_add(digit as usize);
+        self.count = Some(count.min(MAX_COUNT));
+        self.typed.push(char::from_digit(digit, 10).unwrap_or('?'));
+        true
+    }
+
+    /// Feeds the action bound to a key press, `key` being its label for
+    /// `pending`. Returns the command once the sequence is complete. In
+    /// visual mode the selection is the target, so `Delete` acts right away.
+    pub fn feed(&mut self, action: KeyAction, key: &str, visual: bool) -> Option<Command> {
+        if action == KeyAction::GoToTop && !self.prefix {
+            self.prefix = true;
+            self.typed.push_str(key);
+            return None;
+        }
+        if self.prefix && action != KeyAction::GoToTop {
+            // Not a valid sequence; vim just drops it
+            self.reset();
+            return None;
+        }
+
+        let count = self.count.take();
+        if let Some((operator, first_count)) = self.operator.take() {
+            let count = match (first_count, count) {
+                (None, None) => None,
+                (a, b) => Some((a.unwrap_or(1) * b.unwrap_or(1)).min(MAX_COUNT)),
+            };
+            let motion = if action == operator { Some(Motion::Lines) } else { Motion::of(action) };
+            self.reset();
+            return motion.map(|motion| Command { action: operator, count, motion: Some(motion) });
+        }
+
+        if action == KeyAction::Delete && !visual {
+            self.operator = Some((action, count));
+            self.typed.push_str(key);
+            return None;
+        }
+
+        self.reset();
+        Some(Command { action, count, motion: None })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn feed_all(parser: &mut KeyParser, keys: &str) -> Option<Command> {
+        let mut command = None;
+        for c in keys.chars() {
+            if let Some(digit) = c.to_digit(10) {
+                if parser.digit(digit) {
+                    continue;
+                }
+            }
+            let action = match c {
+                'j' => KeyAction::MoveDown,
+                'k' => KeyAction::MoveUp,
+                'g' => KeyAction::GoToTop,
+                'G' => KeyAction::GoToBottom,
+                'd' => KeyAction::Delete,
+                'x' => KeyAction::ToggleSelected,
+                _ => unreachable!(),
+            };
+            command = parser.feed(action, &c.to_string(), false);
+        }
+        command
+    }
+
+    #[test]
+    fn parses_counts_operators_and_motions() {
+        let mut parser = KeyParser::default();
+        let cases = [
+            ("5j", KeyAction::MoveDown, Some(5), None),
+            ("gg", KeyAction::GoToTop, None, None),
+            ("12G", KeyAction::GoToBottom, Some(12), None),
+            ("3x", KeyAction::ToggleSelected, Some(3), None),
+            ("3dd", KeyAction::Delete, Some(3), Some(Motion::Lines)),
+            ("dG", KeyAction::Delete, None, Some(Motion::Bottom)),
+            ("2d3j", KeyAction::Delete, Some(6), Some(Motion::Down)),
+            ("dgg", KeyAction::Delete, None, Some(Motion::Top)),
+        ];
+        for (keys, action, count, motion) in cases {
+            let command = feed_all(&mut parser, keys).unwrap_or_else(|| panic!("{} incomplete", keys));
+            assert_eq!((command.action, command.count, command.motion), (action, count, motion), "{}", keys);
+            assert_eq!(parser.pending(), "");
+        }
+    }
+
+    #[test]
+    fn keeps_incomplete_sequences_pending() {
+        let mut parser = KeyParser::default();
+        assert!(feed_all(&mut parser, "3d").is_none());
+        assert_eq!(parser.pending(), "3d");
+        // An operator followed by something that isn't a motion is dropped
+        assert!(feed_all(&mut parser, "x").is_none());
+        assert_eq!(parser.pending(), "");
+        assert!(!parser.digit(0));
+    }
+}
@@ -0,0 +1,126 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::storage::Storage;
+use crate::{move_project, project_contains};
+
+/// The list used until another one is picked.
+pub(crate) const DEFAULT_LIST: &str = "todo";
+
+// Remembers the list picked last, in the data directory
+const ACTIVE_FILE: &str = "active-list";
+// Projects pinned to the top of the project palette, one per line, in a
+// sidecar of each list
+const PINNED_SUFFIX: &str = ".pinned";
+// Pins from before each list had its own, read by lists without any
+const LEGACY_PINNED_FILE: &str = "pinned-projects";
+
+/// omado's data directory: `$XDG_DATA_HOME/omado`, falling back to
+/// `~/.local/share/omado`. Each `<name>.txt` in it is a todo list.
+pub(crate) fn data_dir() -> PathBuf {
+    let data_dir = std::env::var("XDG_DATA_HOME")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| {
+            let mut home = PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()));
+            home.push(".local/share");
+            home
+        });
+    data_dir.join("omado")
+}
+
+pub(crate) fn path(name: &str) -> PathBuf {
+    data_dir().join(format!("{}.txt", name))
+}
+
+/// The list `path` is, if it is one of the lists in the data directory.
+pub(crate) fn name_of(path: &Path) -> Option<String> {
+    if path.parent()? != data_dir() || path.extension()? != "txt" {
+        return None;
+    }
+    path.file_stem()?.to_str().map(str::to_string)
+}
+
+/// Whether `name` can name a list: it becomes a file name in the data
+/// directory, so no paths, hidden files or archive names (`done.txt`,
+/// `<list>.done.txt`).
+pub(crate) fn is_valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && !name.starts_with('.')
+        && !name.contains('/')
+        && name.trim() == name
+        && name != "done"
+        && !name.ends_with(".done")
+}
+
+/// Every list in the data directory, sorted by name.
+pub(crate) fn names() -> Vec<String> {
+    let mut names: Vec<String> = fs::read_dir(data_dir())
+        .into_iter()
+        .flatten()
+        .flatten()
+        .filter_map(|entry| name_of(&entry.path()))
+        .filter(|name| is_valid_name(name))
+        .collect();
+    names.sort();
+    names
+}
+
+/// The list picked last in the GUI, used when none is given.
+pub(crate) fn active() -> String {
+    fs::read_to_string(data_dir().join(ACTIVE_FILE))
+        .ok()
+        .map(|name| name.trim().to_string())
+        .filter(|name| is_valid_name(name))
+        .unwrap_or_else(|| DEFAULT_LIST.to_string())
+}
+
+pub(crate) fn set_active(name: &str) -> io::Result<()> {
+    fs::create_dir_all(data_dir())?;
+    fs::write(data_dir().join(ACTIVE_FILE), format!("{}\n", name))
+}
+
+/// Projects pinned in the project palette of the todo file `storage`, in the
+/// order they were pinned.
+pub(crate) fn pinned_projects(storage: &Storage) -> Vec<String> {
+    let content = match fs::read_to_string(storage.sidecar(PINNED_SUFFIX)) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => fs::read_to_string(data_dir().join(LEGACY_PINNED_FILE)),
+        result => result,
+    };
+    content
+        .map(|content| content.lines().map(str::trim).filter(|p| !p.is_empty()).map(str::to_string).collect())
+        .unwrap_or_default()
+}
+
+pub(crate) fn set_pinned_projects(storage: &Storage, projects: &[String]) -> io::Result<()> {
+    let content: String = projects.iter().map(|project| format!("{}\n", project)).collect();
+    fs::write(storage.sidecar(PINNED_SUFFIX), content)
+}
+
+/// Moves the pins of project `from` and its subprojects along when it's
+/// renamed to `to`, or drops them if it was deleted.
+pub(crate) fn move_pins(storage: &Storage, from: &str, to: Option<&str>) -> io::Result<()> {
+    let pinned = pinned_projects(storage);
+    let moved = moved_pins(&pinned, from, to);
+    if moved != pinned {
+        set_pinned_projects(storage, &moved)?;
+    }
+    Ok(())
+}
+
+/// `pinned` after project `from` was renamed to `to` or deleted.
+pub(crate) fn moved_pins(pinned: &[String], from: &str, to: Option<&str>) -> Vec<String> {
+    let mut moved: Vec<String> = Vec::new();
+    for project in pinned {
+        let project = match to {
+            _ if !project_contains(from, project) => Some(project.clone()),
+            Some(to) => move_project(project, from, to),
+            None => None,
+        };
+        // Merging can pin the same project twice
+        if let Some(project) = project.filter(|p| !moved.contains(p)) {
+            moved.push(project);
+        }
+    }
+    moved
+}
@@ -1,3 +1,4 @@
+use chrono::{Datelike, NaiveDate};
 use eframe::egui;
 use serde::{Deserialize, Serialize};
 use std::fs;
@@ -5,10 +6,33 @@ use std::path::PathBuf;
 use std::time::{Duration, Instant};
 use anyhow::Result;
 
+mod archive;
+mod cli;
+mod config;
+mod document;
+mod fonts;
+mod history;
+mod keys;
+mod keymap;
+mod lists;
+mod storage;
+mod theme;
+mod watcher;
+
+use config::{Config, Overrides, ProjectStyle};
+use document::TodoDocument;
+use fonts::{FontResolver, Lookup};
+use history::{Edit, History};
+use keys::{Command, KeyParser, Motion};
+use keymap::{HelpMode, Keymap};
+use storage::Storage;
+use theme::Theme;
+use watcher::{WatchKind, Watcher};
+
 // Setup: cargo build --release && ./target/release/omado
 // Hyprland rule: windowrule = opacity 0.9 0.9, class:^(omado)
 
-#[derive(Debug)]
+#[derive(Clone, Copy, PartialEq, Debug)]
 enum KeyAction {
     SaveEdit,
     CancelEdit,
@@ -19,17 +43,32 @@ enum KeyAction {
     EditSelected,
     AddNew,
     ToggleSelected,
-    DeleteKey,
+    Delete,
     CycleFilter,
     ClearSearch,
-    ClearDelete,
     OpenProjectPalette,
+    OpenListPalette,
     ToggleSearch,
     CycleProject,
     ClearAllFilters,
     IncreaseFontSize,
     DecreaseFontSize,
     ResetFontSize,
+    RetryStorage,
+    RaisePriority,
+    LowerPriority,
+    Undo,
+    Redo,
+    MoveTaskDown,
+    MoveTaskUp,
+    ToggleVisual,
+    MoveToProject,
+    SetPriority(Option<char>),
+    Yank,
+    Repeat,
+    Archive,
+    Restore,
+    TogglePrioritySort,
 }
 
 #[derive(Clone, Copy, PartialEq)]
@@ -37,6 +76,26 @@ enum Filter {
     All,
     Active,
     Done,
+    // The date filters only show tasks that are still open
+    Today,
+    Overdue,
+    Upcoming,
+    // Shows the archive instead of the todo file
+    Archived,
+}
+
+// How far ahead `Filter::Upcoming` looks
+const UPCOMING_DAYS: i64 = 7;
+
+/// A project palette action waiting for more input.
+#[derive(Clone)]
+enum ProjectOp {
+    // The new name is typed in the search field
+    Rename(String),
+    // The project to merge into is picked from the list
+    Merge(String),
+    // Waiting for confirmation
+    Delete(String),
 }
 
 #[derive(Clone, PartialEq)]
@@ -51,7 +110,11 @@ impl Filter {
         match self {
             Filter::All => Filter::Active,
             Filter::Active => Filter::Done,
-            Filter::Done => Filter::All,
+            Filter::Done => Filter::Today,
+            Filter::Today => Filter::Overdue,
+            Filter::Overdue => Filter::Upcoming,
+            Filter::Upcoming => Filter::Archived,
+            Filter::Archived => Filter::All,
         }
     }
 
@@ -60,110 +123,435 @@ impl Filter {
             Filter::All => "All",
             Filter::Active => "Active", 
             Filter::Done => "Done",
+            Filter::Today => "Today",
+            Filter::Overdue => "Overdue",
+            Filter::Upcoming => "Upcoming",
+            Filter::Archived => "Archived",
         }
     }
+
+    /// Parses a filter by its name, ignoring case.
+    fn from_name(name: &str) -> Option<Self> {
+        [Filter::All, Filter::Active, Filter::Done, Filter::Today, Filter::Overdue, Filter::Upcoming, Filter::Archived]
+            .into_iter()
+            .find(|filter| filter.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Parses the argument of `omado list --due`.
+    fn from_due_name(name: &str) -> Option<Self> {
+        [Filter::Today, Filter::Overdue, Filter::Upcoming]
+            .into_iter()
+            .find(|filter| filter.name().eq_ignore_ascii_case(name))
+    }
+
+    /// Whether the filter shows `todo`, with due dates compared to `today`.
+    fn matches(self, todo: &Todo, today: NaiveDate) -> bool {
+        match self {
+            // Everything in the archive is archived
+            Filter::All | Filter::Archived => true,
+            Filter::Active => !todo.done,
+            Filter::Done => todo.done,
+            Filter::Today | Filter::Overdue | Filter::Upcoming => {
+                let (Some(due), false) = (todo.due(), todo.done) else { return false };
+                let days = (due - today).num_days();
+                match self {
+                    Filter::Today => days == 0,
+                    Filter::Overdue => days < 0,
+                    _ => days > 0 && days <= UPCOMING_DAYS,
+                }
+            }
+        }
+    }
+}
+
+impl ProjectFilter {
+    /// A project matches its subprojects' tasks too.
+    fn matches(&self, todo: &Todo) -> bool {
+        match self {
+            ProjectFilter::All => true,
+            ProjectFilter::NoProject => todo.project.is_none(),
+            ProjectFilter::Project(project) => todo.project.as_deref().is_some_and(|p| project_contains(project, p)),
+        }
+    }
+}
+
+// Separates the levels of nested projects, as in `work:backend`
+const PROJECT_SEPARATOR: char = ':';
+
+/// Whether `project` is `ancestor` or one of its subprojects.
+fn project_contains(ancestor: &str, project: &str) -> bool {
+    project
+        .strip_prefix(ancestor)
+        .is_some_and(|rest| rest.is_empty() || rest.starts_with(PROJECT_SEPARATOR))
+}
+
+/// Whether `name` can name a project: it has to read back as one word, in
+/// `+project` as well as in `project: text`, so no whitespace or empty levels.
+fn is_valid_project(name: &str) -> bool {
+    !name.is_empty()
+        && !name.contains(char::is_whitespace)
+        && name.split(PROJECT_SEPARATOR).all(|level| !level.is_empty())
 }
 
-#[derive(Serialize, Deserialize, Clone)]
+/// Checks that project `from` can be renamed to `to`, a name no task uses
+/// yet, or merged into `to`, an existing project outside `from`.
+fn check_project_move(todos: &[Todo], from: &str, to: &str, merge: bool) -> Result<(), String> {
+    if !is_valid_project(to) {
+        return Err(format!("Invalid project name '{}': no spaces, and ':' only between levels", to));
+    }
+    let exists = |name: &str| todos.iter().any(|todo| todo.project.as_deref().is_some_and(|p| project_contains(name, p)));
+    if !exists(from) {
+        return Err(format!("No project '{}'", from));
+    }
+    match (merge, exists(to)) {
+        (false, true) => Err(format!("Project '{}' already exists; merge the two instead", to)),
+        (true, false) => Err(format!("No project '{}'", to)),
+        (true, true) if project_contains(from, to) => {
+            Err(format!("Can't merge '{}' into itself or one of its subprojects", from))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// What `project` becomes when project `from` is renamed to `to`, if it's
+/// affected: `from` itself and its subprojects move along.
+fn move_project(project: &str, from: &str, to: &str) -> Option<String> {
+    project_contains(from, project).then(|| format!("{}{}", to, &project[from.len()..]))
+}
+
+/// What a word of a task line was read as, so that rewriting the line puts
+/// each word back where it was.
+#[derive(Clone, Copy, PartialEq, Debug)]
+enum Word {
+    Text,
+    Context,
+    Tag,
+    // A todo.txt `+project`
+    Project,
+}
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, Default)]
 struct Todo {
     text: String,
     done: bool,
     project: Option<String>,
+    // todo.txt priority, 'A' being the highest
+    priority: Option<char>,
+    created: Option<NaiveDate>,
+    completed: Option<NaiveDate>,
+    // `@context` words, without the `@`
+    contexts: Vec<String>,
+    // `key:value` extension tags, in file order
+    tags: Vec<(String, String)>,
+    // The order the words of `text`, contexts and tags were read in
+    #[serde(skip)]
+    layout: Vec<Word>,
 }
 
-#[derive(Deserialize)]
-struct AlacrittyColors {
-    primary: Option<AlacrittyPrimary>,
-    normal: Option<AlacrittyNormal>,
-}
+impl Todo {
+    /// Parses a task as typed by the user, e.g. `(A) work: Call Bob @phone due:fri`.
+    fn from_input(input: &str) -> Self {
+        let mut todo = Todo::default();
+        todo.set_input(input);
+        todo
+    }
+    
+    /// Like `set_full_text`, but also turns relative due dates such as
+    /// `due:tomorrow` into absolute ones so they don't drift once saved.
+    fn set_input(&mut self, input: &str) {
+        // `!`, `!!` and `!!!` are shorthands for priorities C, B and A
+        let mut words: Vec<&str> = input.split_whitespace().collect();
+        let shorthand = words.iter().position(|word| matches!(*word, "!" | "!!" | "!!!")).map(|pos| words.remove(pos));
+        self.set_full_text(&words.join(" "));
+        if let Some(shorthand) = shorthand {
+            self.priority = Some((b'D' - shorthand.len() as u8) as char);
+        }
+        
+        let today = today();
+        for (key, value) in &mut self.tags {
+            if key == "due" {
+                if let Some(date) = resolve_date(value, today) {
+                    *value = date.format("%Y-%m-%d").to_string();
+                }
+            }
+        }
+    }
+    
+    /// Replaces everything that is part of the typed text, keeping the
+    /// completion state and dates. The inverse of `full_text`.
+    fn set_full_text(&mut self, input: &str) {
+        let (priority, rest) = split_priority(input.trim());
+        let (text, project) = TodoApp::parse_todo_text(rest);
+        self.priority = priority;
+        self.project = project;
+        self.contexts.clear();
+        self.tags.clear();
+        self.layout.clear();
+        self.text = self.take_metadata(&text);
+    }
+    
+    /// Moves `@context` and `key:value` words out of `text` into their
+    /// fields and returns the remaining words.
+    fn take_metadata(&mut self, text: &str) -> String {
+        let words: Vec<&str> = text.split_whitespace().filter_map(|word| self.take_word(word)).collect();
+        words.join(" ")
+    }
+    
+    /// Reads the next word of the task: an `@context` or `key:value` word goes
+    /// into its field, while a text word is returned.
+    fn take_word<'a>(&mut self, word: &'a str) -> Option<&'a str> {
+        if let Some(context) = word.strip_prefix('@').filter(|c| !c.is_empty()) {
+            self.contexts.push(context.to_string());
+            self.layout.push(Word::Context);
+            None
+        } else if let Some((key, value)) = split_tag(word) {
+            self.tags.push((key.to_string(), value.to_string()));
+            self.layout.push(Word::Tag);
+            None
+        } else {
+            self.layout.push(Word::Text);
+            Some(word)
+        }
+    }
+    
+    fn remove_tag(&mut self, idx: usize) -> (String, String) {
+        if let Some(at) = self.layout.iter().enumerate().filter(|(_, word)| **word == Word::Tag).nth(idx).map(|(at, _)| at) {
+            self.layout.remove(at);
+        }
+        self.tags.remove(idx)
+    }
+    
+    /// Text as typed by the user: priority, `project: ` prefix, contexts and tags restored.
+    fn full_text(&self) -> String {
+        let mut text = String::new();
+        if let Some(priority) = self.priority {
+            text.push_str(&format!("({}) ", priority));
+        }
+        if let Some(ref project) = self.project {
+            text.push_str(&format!("{}: ", project));
+        }
+        text.push_str(&self.words(None));
+        text
+    }
+    
+    /// The text with contexts, tags and `project` (a todo.txt `+project`, if
+    /// given) put back where they were read. Words the task didn't have when
+    /// it was read go after the text, with `project` before any other `+word`.
+    fn words(&self, project: Option<&str>) -> String {
+        let mut text = self.text.split_whitespace().map(str::to_string);
+        let mut contexts = self.contexts.iter().map(|context| format!("@{}", context));
+        let mut tags = self.tags.iter().map(|(key, value)| format!("{}:{}", key, value));
+        let mut project = project.map(|project| format!("+{}", project));
 
-#[derive(Deserialize)]
-struct AlacrittyPrimary {
-    background: Option<String>,
-    foreground: Option<String>,
+        let mut words: Vec<String> = Vec::new();
+        let mut text_end = 0;
+        for word in &self.layout {
+            let next = match word {
+                Word::Text => text.next(),
+                Word::Context => contexts.next(),
+                Word::Tag => tags.next(),
+                Word::Project => project.take(),
+            };
+            words.extend(next);
+            if *word == Word::Text {
+                text_end = words.len();
+            }
+        }
+        for word in text {
+            words.insert(text_end, word);
+            text_end += 1;
+        }
+        if let Some(project) = project {
+            let at = words[..text_end]
+                .iter()
+                .position(|word| word.len() > 1 && word.starts_with('+'))
+                .unwrap_or(text_end);
+            words.insert(at, project);
+        }
+        words.extend(contexts);
+        words.extend(tags);
+        words.join(" ")
+    }
+    
+    /// Contexts and tags, each with a leading space, for showing them apart from the text.
+    fn metadata_text(&self) -> String {
+        let mut text = String::new();
+        for context in &self.contexts {
+            text.push_str(&format!(" @{}", context));
+        }
+        for (key, value) in &self.tags {
+            text.push_str(&format!(" {}:{}", key, value));
+        }
+        text
+    }
+    
+    fn set_done(&mut self, done: bool) {
+        self.done = done;
+        self.completed = if done { Some(today()) } else { None };
+    }
+    
+    /// Sort key that puts `(A)` first and tasks without a priority last.
+    fn priority_rank(&self) -> u8 {
+        self.priority.map_or(u8::MAX, |priority| priority as u8)
+    }
+    
+    /// Steps the priority towards `(A)`, or down through `(C)` to none.
+    fn shift_priority(&mut self, raise: bool) {
+        self.priority = match (self.priority, raise) {
+            (None, true) => Some('C'),
+            (Some(priority), true) => Some(if priority > 'A' { (priority as u8 - 1) as char } else { 'A' }),
+            (Some(priority), false) if priority < 'C' => Some((priority as u8 + 1) as char),
+            (_, false) => None,
+        };
+    }
+    
+    /// The `due:YYYY-MM-DD` tag, if present and valid.
+    fn due(&self) -> Option<NaiveDate> {
+        self.tags
+            .iter()
+            .find(|(key, _)| key == "due")
+            .and_then(|(_, value)| NaiveDate::parse_from_str(value, "%Y-%m-%d").ok())
+    }
+    
+    /// For a task with a valid `rec:` rule, the open copy to create once this
+    /// one is completed, due on the next date the rule gives.
+    fn next_occurrence(&self) -> Option<Todo> {
+        let rule = self.tags.iter().find(|(key, _)| key == "rec").map(|(_, value)| value)?;
+        let due = next_due_date(rule, self.due(), today())?.format("%Y-%m-%d").to_string();
+        
+        let mut next = self.clone();
+        next.done = false;
+        next.completed = None;
+        next.created = None;
+        match next.tags.iter_mut().find(|(key, _)| key == "due") {
+            Some((_, value)) => *value = due,
+            None => next.tags.push(("due".to_string(), due)),
+        }
+        Some(next)
+    }
 }
 
-#[derive(Deserialize)]
-struct AlacrittyNormal {
-    black: Option<String>,
-    #[allow(dead_code)]
-    red: Option<String>,
-    #[allow(dead_code)]
-    green: Option<String>,
-    #[allow(dead_code)]
-    yellow: Option<String>,
-    blue: Option<String>,
-    #[allow(dead_code)]
-    magenta: Option<String>,
-    cyan: Option<String>,
-    white: Option<String>,
+fn today() -> NaiveDate {
+    chrono::Local::now().date_naive()
 }
 
-#[derive(Deserialize)]
-struct AlacrittyConfig {
-    colors: Option<AlacrittyColors>,
-    general: Option<AlacrittyGeneral>,
-    font: Option<AlacrittyFont>,
+/// Resolves `YYYY-MM-DD`, `today`, `tomorrow`, weekday names (`fri`, `friday`:
+/// the next one after today) and offsets (`3d`, `2w`) relative to `today`.
+fn resolve_date(value: &str, today: NaiveDate) -> Option<NaiveDate> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(date);
+    }
+    let value = value.to_lowercase();
+    match value.as_str() {
+        "today" => return Some(today),
+        "tomorrow" | "tom" => return today.succ_opt(),
+        _ => {}
+    }
+    if let Ok(weekday) = value.parse::<chrono::Weekday>() {
+        let ahead = (weekday.num_days_from_monday() + 7 - today.weekday().num_days_from_monday()) % 7;
+        let ahead = if ahead == 0 { 7 } else { ahead };
+        return today.checked_add_signed(chrono::Duration::days(ahead as i64));
+    }
+    let days = if let Some(count) = value.strip_suffix('d') {
+        count.parse::<i64>().ok()?
+    } else if let Some(count) = value.strip_suffix('w') {
+        count.parse::<i64>().ok()?.checked_mul(7)?
+    } else {
+        return None;
+    };
+    today.checked_add_signed(chrono::Duration::try_days(days)?)
 }
 
-#[derive(Deserialize)]
-struct AlacrittyFont {
-    normal: Option<AlacrittyFontFamily>,
-    size: Option<f32>,
+/// Applies a recurrence rule: `daily`, `weekly`, `monthly`, `yearly`, counts
+/// of days/weeks/months/years (`3d`, `2w`, `1m`, `1y`) or `monthly-on-1st`.
+/// Intervals count from `today`, the completion date, unless prefixed with `+`
+/// (`+2w`), in which case they count from the old due date so the schedule
+/// doesn't slip when a task is completed late.
+fn next_due_date(rule: &str, due: Option<NaiveDate>, today: NaiveDate) -> Option<NaiveDate> {
+    let rule = rule.to_lowercase();
+    
+    if let Some(ordinal) = rule.strip_prefix("monthly-on-") {
+        let day: u32 = ordinal
+            .trim_end_matches(|c: char| c.is_ascii_alphabetic())
+            .parse()
+            .ok()
+            .filter(|day| (1..=31).contains(day))?;
+        // The first matching day after both the old due date and today
+        let after = due.map_or(today, |due| due.max(today));
+        let this_month = after.with_day(1)?;
+        return [this_month, this_month.checked_add_months(chrono::Months::new(1))?]
+            .into_iter()
+            .map(|month| day_of_month(month, day))
+            .find(|date| *date > after);
+    }
+    
+    let (strict, interval) = match rule.strip_prefix('+') {
+        Some(interval) => (true, interval),
+        None => (false, rule.as_str()),
+    };
+    let (count, unit) = match interval {
+        "daily" => ("1", "d"),
+        "weekly" => ("1", "w"),
+        "monthly" => ("1", "m"),
+        "yearly" => ("1", "y"),
+        _ => {
+            let unit = interval.chars().last()?;
+            interval.split_at(interval.len() - unit.len_utf8())
+        }
+    };
+    let count: u32 = count.parse().ok().filter(|count| *count > 0)?;
+    
+    let base = if strict { due.unwrap_or(today) } else { today };
+    match unit {
+        "d" => base.checked_add_days(chrono::Days::new(count as u64)),
+        "w" => base.checked_add_days(chrono::Days::new(count as u64 * 7)),
+        "m" => base.checked_add_months(chrono::Months::new(count)),
+        "y" => base.checked_add_months(chrono::Months::new(count.checked_mul(12)?)),
+        _ => None,
+    }
 }
 
-#[derive(Deserialize)]
-struct AlacrittyFontFamily {
-    family: Option<String>,
+/// `day` of the month `first` is in, clamped to the month's last day.
+fn day_of_month(first: NaiveDate, day: u32) -> NaiveDate {
+    (1..=day).rev().find_map(|d| first.with_day(d)).unwrap_or(first)
 }
 
-#[derive(Deserialize)]
-struct AlacrittyGeneral {
-    import: Option<Vec<String>>,
+/// Short description of a due date for the task list, e.g. `due Fri` or `overdue 2d`.
+fn due_label(due: NaiveDate, today: NaiveDate) -> String {
+    let days = (due - today).num_days();
+    match days {
+        ..=-1 => format!("overdue {}d", -days),
+        0 => "due today".to_string(),
+        1 => "due tomorrow".to_string(),
+        2..=6 => format!("due {}", due.format("%a")),
+        _ if due.year() == today.year() => format!("due {}", due.format("%b %-d")),
+        _ => format!("due {}", due.format("%Y-%m-%d")),
+    }
 }
 
-struct Theme {
-    background: egui::Color32,
-    foreground: egui::Color32,
-    accent: egui::Color32,
-    border: egui::Color32,
-    done_color: egui::Color32,
-    // Additional colors from Alacritty theme for project coloring
-    red: Option<egui::Color32>,
-    green: Option<egui::Color32>,
-    yellow: Option<egui::Color32>,
-    blue: Option<egui::Color32>,
-    magenta: Option<egui::Color32>,
-    cyan: Option<egui::Color32>,
-    white: Option<egui::Color32>,
-    font_family: Option<String>,
-    font_size: Option<f32>,
+/// Splits a leading todo.txt priority such as `(A) ` off `text`.
+fn split_priority(text: &str) -> (Option<char>, &str) {
+    let bytes = text.as_bytes();
+    if bytes.len() >= 4 && bytes[0] == b'(' && bytes[1].is_ascii_uppercase() && bytes[2] == b')' && bytes[3] == b' ' {
+        (Some(bytes[1] as char), text[4..].trim_start())
+    } else {
+        (None, text)
+    }
 }
 
-impl Default for Theme {
-    fn default() -> Self {
-        Self {
-            background: egui::Color32::from_rgb(26, 27, 38),
-            foreground: egui::Color32::from_rgb(205, 214, 244),
-            accent: egui::Color32::from_rgb(116, 199, 236),
-            border: egui::Color32::from_rgb(88, 91, 112),
-            done_color: egui::Color32::from_rgb(166, 173, 200),
-            // Default Catppuccin-like colors for projects
-            red: Some(egui::Color32::from_rgb(243, 139, 168)),
-            green: Some(egui::Color32::from_rgb(166, 227, 161)),
-            yellow: Some(egui::Color32::from_rgb(249, 226, 175)),
-            blue: Some(egui::Color32::from_rgb(137, 180, 250)),
-            magenta: Some(egui::Color32::from_rgb(203, 166, 247)),
-            cyan: Some(egui::Color32::from_rgb(148, 226, 213)),
-            white: Some(egui::Color32::from_rgb(205, 214, 244)),
-            font_family: None,
-            font_size: None,
-        }
-    }
+/// Recognizes todo.txt `key:value` words. Times (`10:30`) and URLs are left alone.
+fn split_tag(word: &str) -> Option<(&str, &str)> {
+    let (key, value) = word.split_once(':')?;
+    let key_ok = key.starts_with(|c: char| c.is_ascii_alphabetic())
+        && key.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_');
+    let value_ok = !value.is_empty() && !value.contains(':') && !value.starts_with('/');
+    (key_ok && value_ok).then_some((key, value))
 }
 
 struct TodoApp {
-    todos: Vec<Todo>,
+    todos: TodoDocument,
+    // Read while `Filter::Archived` shows it
+    archive: TodoDocument,
     selected: usize,
     filter: Filter,
     project_filter: ProjectFilter,
@@ -172,294 +560,656 @@ struct TodoApp {
     edit_text: String,
     theme: Theme,
     last_theme_check: Instant,
-    config_path: Option<PathBuf>,
-    storage_path: PathBuf,
-    delete_mode: bool,
+    // Every file the current theme was read from, in load order
+    theme_files: Vec<PathBuf>,
+    // Problems reading the theme, shown until dismissed or the theme changes
+    theme_warning: Option<String>,
+    watcher: Option<Watcher>,
+    storage: Storage,
+    storage_error: Option<String>,
+    storage_warning: Option<String>,
+    dirty: bool,
+    // The todo file as last read or saved, and every edit made on top of it
+    // since, reapplied if someone else changes the file before we save
+    saved: TodoDocument,
+    unsaved: Vec<Edit>,
+    history: History,
+    // Edits made by the `modify_todos` call in progress
+    pending_edits: Vec<Edit>,
+    config: Config,
+    // Command line settings, reapplied when the config file is reloaded
+    overrides: Overrides,
+    keymap: Keymap,
+    // Problems with the config file, shown until dismissed
+    config_warning: Option<String>,
+    key_parser: KeyParser,
+    // Last command that changed tasks, for `.`
+    last_change: Option<Command>,
+    // Where visual mode started, as a position in the filtered list
+    visual_anchor: Option<usize>,
+    // Text to put on the clipboard on the next frame
+    clipboard: Option<String>,
     show_project_palette: bool,
+    // The palette assigns a project to the selection instead of filtering
+    project_palette_assign: bool,
     project_palette_search: String,
     project_palette_selected: usize,
+    project_palette_op: Option<ProjectOp>,
+    // Listed first, in the order they were pinned
+    pinned_projects: Vec<String>,
+    show_list_palette: bool,
+    list_palette_search: String,
+    list_palette_selected: usize,
+    // Lists with their open task counts, read when the palette opens
+    list_palette_lists: Vec<(String, usize)>,
     show_search: bool,
+    // Off while the user orders tasks by hand
+    sort_by_priority: bool,
     user_font_size: Option<f32>,
+    font_resolver: FontResolver,
+    // Family currently installed with `ctx.set_fonts`
+    applied_font_family: Option<String>,
 }
 
 impl TodoApp {
-    fn new() -> Self {
-        let storage_path = Self::get_storage_path();
-        let config_path = Self::get_alacritty_config_path();
+    fn new(ctx: &egui::Context, config: Config, config_warning: Option<String>, overrides: Overrides) -> Self {
+        let (storage_path, storage_warning) = match Self::get_storage_path(&config) {
+            Ok(path) => (path, None),
+            Err(e) => (PathBuf::from("todo.txt"), Some(format!("{}; using ./todo.txt instead", e))),
+        };
+        let storage = Storage::new(storage_path);
+        let pinned_projects = lists::pinned_projects(&storage);
+        let (keymap, config_warning) = Self::keymap_for(&config, config_warning);
         
         let mut app = Self {
-            todos: Vec::new(),
+            todos: TodoDocument::default(),
+            archive: TodoDocument::default(),
             selected: 0,
-            filter: Filter::All,
+            filter: Filter::from_name(&config.default_filter).unwrap_or(Filter::All),
             project_filter: ProjectFilter::All,
             search: String::new(),
             editing: None,
             edit_text: String::new(),
             theme: Theme::default(),
             last_theme_check: Instant::now(),
-            config_path,
-            storage_path,
-            delete_mode: false,
+            theme_files: Vec::new(),
+            theme_warning: None,
+            // Without inotify we fall back to polling in `update`
+            watcher: Watcher::spawn(ctx.clone()).ok(),
+            storage,
+            storage_error: None,
+            storage_warning,
+            dirty: false,
+            saved: TodoDocument::default(),
+            unsaved: Vec::new(),
+            history: History::default(),
+            pending_edits: Vec::new(),
+            config,
+            overrides,
+            keymap,
+            config_warning,
+            key_parser: KeyParser::default(),
+            last_change: None,
+            visual_anchor: None,
+            clipboard: None,
             show_project_palette: false,
+            project_palette_assign: false,
             project_palette_search: String::new(),
             project_palette_selected: 0,
+            project_palette_op: None,
+            pinned_projects,
+            show_list_palette: false,
+            list_palette_search: String::new(),
+            list_palette_selected: 0,
+            list_palette_lists: Vec::new(),
             show_search: false,
+            sort_by_priority: true,
             user_font_size: None,
+            font_resolver: FontResolver::new(ctx.clone()),
+            applied_font_family: None,
         };
         
         app.load_todos();
+        app.auto_archive();
         app.load_theme();
+        app.watch_todos();
+        if let Some(ref watcher) = app.watcher {
+            watcher.watch(WatchKind::Config, &Config::path().into_iter().collect::<Vec<_>>());
+        }
         app
     }
     
-    pub fn get_storage_path() -> PathBuf {
-        // Use XDG_DATA_HOME or fallback to ~/.local/share for Linux
-        let data_dir = std::env::var("XDG_DATA_HOME")
-            .map(PathBuf::from)
-            .unwrap_or_else(|_| {
-                let mut home = PathBuf::from(std::env::var("HOME").unwrap_or_else(|_| ".".to_string()));
-                home.push(".local/share");
-                home
-            });
-        
-        let mut path = data_dir;
-        path.push("omado");
-        if let Err(_) = fs::create_dir_all(&path) {
-            path = PathBuf::from(".");
-        }
-        path.push("todo.txt");
-        path
-    }
-    
-    fn get_alacritty_config_path() -> Option<PathBuf> {
-        // Use XDG_CONFIG_HOME or fallback to ~/.config for Linux
-        let config_dir = if let Ok(xdg_config) = std::env::var("XDG_CONFIG_HOME") {
-            PathBuf::from(xdg_config)
-        } else if let Ok(home) = std::env::var("HOME") {
-            let mut path = PathBuf::from(home);
-            path.push(".config");
-            path
-        } else {
-            return None;
+    /// Watches the todo file and its archive.
+    fn watch_todos(&self) {
+        if let Some(ref watcher) = self.watcher {
+            let path = self.storage.path();
+            watcher.watch(WatchKind::Todos, &[path.to_path_buf(), archive::path_for(path)]);
+        }
+    }
+    
+    /// The todo file: the configured one, or else the list given with
+    /// `--list` or picked last.
+    pub fn get_storage_path(config: &Config) -> Result<PathBuf> {
+        let path = match (&config.file, &config.list) {
+            (Some(file), _) => file.clone(),
+            (None, Some(list)) => lists::path(list),
+            (None, None) => lists::path(&lists::active()),
         };
         
-        let mut path = config_dir;
-        path.push("alacritty");
-        path.push("alacritty.toml");
-        if path.exists() { Some(path) } else { None }
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)
+                .map_err(|e| anyhow::anyhow!("Couldn't create {}: {}", dir.display(), e))?;
+        }
+        Ok(path)
+    }
+    
+    /// Builds the keymap of `config`, adding its problems to the config
+    /// file's `warning`.
+    fn keymap_for(config: &Config, warning: Option<String>) -> (Keymap, Option<String>) {
+        let (keymap, problems) = Keymap::with_overrides(&config.keys);
+        let mut warnings: Vec<String> = warning.into_iter().collect();
+        if !problems.is_empty() {
+            let path = Config::path().unwrap_or_default();
+            warnings.push(format!("{} [keys]: {}", path.display(), problems.join("; ")));
+        }
+        (keymap, (!warnings.is_empty()).then(|| warnings.join("\n")))
+    }
+    
+    /// Rereads the config file after it changed. The filter it sets is only
+    /// the one shown at startup, so the current filter is left alone.
+    fn reload_config(&mut self) {
+        let (config, warning) = Config::load_with(&self.overrides);
+        let (keymap, warning) = Self::keymap_for(&config, warning);
+        self.keymap = keymap;
+        self.config_warning = warning;
+        
+        match Self::get_storage_path(&config) {
+            Ok(path) if path != self.storage.path() => {
+                self.switch_storage(path);
+            }
+            Ok(_) => {}
+            Err(e) => {
+                self.storage_warning = Some(format!("{}; still using {}", e, self.storage.path().display()));
+            }
+        }
+        let theme_changed = config.theme != self.config.theme;
+        self.config = config;
+        if theme_changed {
+            self.load_theme();
+        }
+    }
+    
+    /// Switches to the list `name`, creating it on the first save, and
+    /// remembers it for the next launch.
+    fn switch_list(&mut self, name: &str) {
+        if !self.switch_storage(lists::path(name)) {
+            return;
+        }
+        if let Err(e) = lists::set_active(name) {
+            self.storage_warning = Some(format!("Couldn't remember the active list: {}", e));
+        }
+        // The list picked here replaces whatever the command line or the
+        // config's `file` asked for, also once the config is reloaded
+        self.overrides.file = None;
+        self.overrides.list = Some(name.to_string());
+        self.project_filter = ProjectFilter::All;
+    }
+    
+    /// Starts working on another todo file. Stays on the current one, and
+    /// returns false, if its unsaved edits can't be saved first.
+    fn switch_storage(&mut self, path: PathBuf) -> bool {
+        // Edits that haven't made it to disk belong to the old file
+        if self.dirty {
+            self.save_todos();
+            if self.dirty {
+                self.storage_warning = Some(format!(
+                    "Staying on {} until its changes are saved",
+                    self.storage.path().display()
+                ));
+                return false;
+            }
+        }
+        self.storage = Storage::new(path);
+        self.pinned_projects = lists::pinned_projects(&self.storage);
+        self.dirty = false;
+        self.storage_error = None;
+        self.unsaved.clear();
+        self.history.clear();
+        self.pending_edits.clear();
+        self.visual_anchor = None;
+        self.editing = None;
+        self.selected = 0;
+        self.load_todos();
+        self.auto_archive();
+        self.watch_todos();
+        true
     }
     
     fn load_theme(&mut self) {
-        // Reset to default theme first to ensure clean state
-        self.theme = Theme::default();
+        let loaded = theme::load(&self.config.theme);
+        self.theme = loaded.theme;
+        self.theme_files = loaded.files;
+        self.theme_warning = (!loaded.warnings.is_empty()).then(|| loaded.warnings.join("\n"));
         
-        if let Some(ref config_path) = self.config_path.clone() {
-            self.load_theme_from_file(config_path);
-        }
-    }
-    
-    fn load_theme_from_file(&mut self, config_path: &PathBuf) {
-        if let Ok(content) = fs::read_to_string(config_path) {
-            if let Ok(config) = toml::from_str::<AlacrittyConfig>(&content) {
-                // Check for imported files first
-                if let Some(general) = config.general {
-                    if let Some(imports) = general.import {
-                        for import_path in imports {
-                            let expanded = shellexpand::tilde(&import_path);
-                            let import_path = PathBuf::from(expanded.as_ref());
-                            if import_path.exists() {
-                                self.load_theme_from_file(&import_path);
-                            }
-                        }
+        if let Some(ref watcher) = self.watcher {
+            watcher.watch(WatchKind::Theme, &self.theme_files);
+        }
+    }
+    
+    pub fn parse_todo_text(text: &str) -> (String, Option<String>) {
+        // Only `project: task` counts, so `due:tomorrow` or `10:30` don't become projects
+        let colon = text.char_indices().find(|&(i, c)| {
+            c == ':' && text[i + 1..].starts_with(char::is_whitespace)
+        });
+        if let Some((colon_pos, _)) = colon {
+            let project_part = text[..colon_pos].trim();
+            let mut task_part = text[colon_pos + 1..].trim();
+            if !project_part.is_empty() && !task_part.is_empty() {
+                // `work: backend: fix` nests: single words followed by `: ` are subprojects
+                let mut project = project_part.to_string();
+                while let Some((sub, rest)) = task_part.split_once(": ") {
+                    let rest = rest.trim();
+                    if sub.is_empty() || sub.contains(char::is_whitespace) || rest.is_empty() {
+                        break;
                     }
+                    project.push(PROJECT_SEPARATOR);
+                    project.push_str(sub);
+                    task_part = rest;
                 }
-                
-                // Load colors from current file (this will override imported ones)
-                if let Some(colors) = config.colors {
-                    if let Some(primary) = colors.primary {
-                        if let Some(bg) = primary.background {
-                            if let Ok(color) = Self::parse_hex_color(&bg) {
-                                self.theme.background = color;
-                            }
-                        }
-                        if let Some(fg) = primary.foreground {
-                            if let Ok(color) = Self::parse_hex_color(&fg) {
-                                self.theme.foreground = color;
-                            }
-                        }
-                    }
-                    if let Some(normal) = colors.normal {
-                        if let Some(blue) = normal.blue {
-                            if let Ok(color) = Self::parse_hex_color(&blue) {
-                                self.theme.accent = color;
-                                self.theme.blue = Some(color);
-                            }
-                        }
-                        if let Some(white) = normal.white {
-                            if let Ok(color) = Self::parse_hex_color(&white) {
-                                self.theme.border = color;
-                                self.theme.white = Some(color);
-                            }
-                        }
-                        if let Some(cyan) = normal.cyan {
-                            if let Ok(color) = Self::parse_hex_color(&cyan) {
-                                self.theme.done_color = color;
-                                self.theme.cyan = Some(color);
-                            }
-                        } else if let Some(black) = normal.black {
-                            if let Ok(color) = Self::parse_hex_color(&black) {
-                                self.theme.done_color = color;
-                            }
-                        }
-                        
-                        // Load additional colors for project names
-                        if let Some(red) = normal.red {
-                            if let Ok(color) = Self::parse_hex_color(&red) {
-                                self.theme.red = Some(color);
-                            }
-                        }
-                        if let Some(green) = normal.green {
-                            if let Ok(color) = Self::parse_hex_color(&green) {
-                                self.theme.green = Some(color);
-                            }
-                        }
-                        if let Some(yellow) = normal.yellow {
-                            if let Ok(color) = Self::parse_hex_color(&yellow) {
-                                self.theme.yellow = Some(color);
-                            }
-                        }
-                        if let Some(magenta) = normal.magenta {
-                            if let Ok(color) = Self::parse_hex_color(&magenta) {
-                                self.theme.magenta = Some(color);
-                            }
-                        }
-                    }
+                return (task_part.to_string(), Some(project));
+            }
+        }
+        (text.to_string(), None)
+    }
+    
+    fn load_todos(&mut self) {
+        if self.filter == Filter::Archived {
+            self.load_archive();
+        }
+        match self.storage.load() {
+            // Never replace edits that haven't made it to disk yet
+            Ok(todos) if self.dirty => self.rebase_unsaved(todos),
+            Ok(todos) => {
+                // Undo refers to tasks by index, which someone else's changes invalidate
+                if todos.contents() != self.todos.contents() {
+                    self.history.clear();
                 }
-                
-                // Load font settings
-                if let Some(font) = config.font {
-                    if let Some(size) = font.size {
-                        self.theme.font_size = Some(size);
-                    }
-                    if let Some(normal) = font.normal {
-                        if let Some(family) = normal.family {
-                            self.theme.font_family = Some(family);
-                        }
-                    }
+                self.saved = todos.clone();
+                self.todos = todos;
+                self.storage_error = None;
+            }
+            Err(e) => {
+                self.storage_error = Some(format!("Couldn't read {}: {}", self.storage.path().display(), e));
+            }
+        }
+    }
+    
+    /// Puts the edits that haven't been saved yet on top of `todos`, the file
+    /// as someone else has changed it since we last read it.
+    fn rebase_unsaved(&mut self, todos: TodoDocument) {
+        if todos.contents() == self.saved.contents() {
+            return;
+        }
+        let mut rebased = todos.clone();
+        let (unsaved, complete) = history::rebase(&self.unsaved, &self.saved, &mut rebased);
+        self.saved = todos;
+        self.unsaved = unsaved;
+        self.todos = rebased;
+        self.history.clear();
+        if !complete {
+            self.storage_warning = Some(format!(
+                "Some unsaved changes were dropped: their tasks were changed in {} meanwhile",
+                self.storage.path().display()
+            ));
+        }
+    }
+    
+    fn load_archive(&mut self) {
+        let path = archive::path_for(self.storage.path());
+        match Storage::new(path.clone()).load() {
+            Ok(archive) => self.archive = archive,
+            Err(e) => self.storage_error = Some(format!("Couldn't read {}: {}", path.display(), e)),
+        }
+    }
+    
+    /// Moves the tasks `select` picks to the archive.
+    fn archive_tasks(&mut self, select: impl Fn(&Todo) -> bool) {
+        let archive = Storage::new(archive::path_for(self.storage.path()));
+        let mut result = Ok(0);
+        self.modify_todos(|app| {
+            result = archive::move_tasks(&archive, &mut app.todos, select).map(|archived| {
+                // Not undoable, as the tasks are in the archive now, but still to be saved
+                let moved = archived.len();
+                app.unsaved.extend(archived.into_iter().map(|(idx, todo)| Edit::Remove { idx, todo }));
+                moved
+            });
+        });
+        match result {
+            // Removing tasks moves the others, so recorded edits no longer line up
+            Ok(moved) if moved > 0 => {
+                self.history.clear();
+                self.selected = 0;
+                if self.filter == Filter::Archived {
+                    self.load_archive();
                 }
             }
+            Ok(_) => {}
+            Err(e) => self.storage_error = Some(format!("Couldn't archive to {}: {}", archive.path().display(), e)),
         }
     }
     
-    fn parse_hex_color(hex: &str) -> Result<egui::Color32> {
-        let hex = hex.trim_start_matches('#');
-        if hex.len() != 6 {
-            return Err(anyhow::anyhow!("Invalid hex color length"));
+    /// Archives tasks done longer ago than the config allows, if it sets a limit.
+    fn auto_archive(&mut self) {
+        let Some(days) = self.config.archive_after_days else { return };
+        if self.todos.iter().any(|todo| archive::is_archivable(todo, Some(days))) {
+            self.archive_tasks(|todo| archive::is_archivable(todo, Some(days)));
         }
-        let r = u8::from_str_radix(&hex[0..2], 16)?;
-        let g = u8::from_str_radix(&hex[2..4], 16)?;  
-        let b = u8::from_str_radix(&hex[4..6], 16)?;
-        Ok(egui::Color32::from_rgb(r, g, b))
     }
     
-    pub fn parse_todo_text(text: &str) -> (String, Option<String>) {
-        if let Some(colon_pos) = text.find(':') {
-            let project_part = &text[..colon_pos].trim();
-            let task_part = &text[colon_pos + 1..].trim();
-            if !project_part.is_empty() && !task_part.is_empty() {
-                return (task_part.to_string(), Some(project_part.to_string()));
+    /// Moves archived tasks back to the end of the todo file.
+    fn restore_archived(&mut self, range: (usize, usize)) {
+        let wanted: Vec<Todo> = self.indices_in(range).into_iter().map(|idx| self.archive[idx].clone()).collect();
+        let archive = Storage::new(archive::path_for(self.storage.path()));
+        // Into the todo file first, so that a failure leaves the tasks in both
+        // files rather than in neither. Appending doesn't move other tasks, so
+        // undo history stays valid.
+        self.modify_todos(|app| {
+            for todo in &wanted {
+                app.todos.push(todo.clone());
+                app.unsaved.push(Edit::Insert { idx: app.todos.len() - 1, todo: todo.clone() });
+            }
+        });
+        if !self.dirty {
+            if let Err(e) = archive::take(&archive, &wanted) {
+                self.storage_error = Some(format!("Couldn't remove restored tasks from {}: {}", archive.path().display(), e));
             }
         }
-        (text.to_string(), None)
+        self.load_archive();
+        let visible = self.filtered_todos().len();
+        self.selected = range.0.min(visible.saturating_sub(1));
     }
     
-    fn load_todos(&mut self) {
-        if let Ok(content) = fs::read_to_string(&self.storage_path) {
-            self.todos.clear();
-            for line in content.lines() {
-                let line = line.trim();
-                if line.starts_with("[ ] ") {
-                    let (text, project) = Self::parse_todo_text(&line[4..]);
-                    self.todos.push(Todo {
-                        text,
-                        done: false,
-                        project,
-                    });
-                } else if line.starts_with("[x] ") {
-                    let (text, project) = Self::parse_todo_text(&line[4..]);
-                    self.todos.push(Todo {
-                        text,
-                        done: true,
-                        project,
-                    });
+    /// Saves edits a failed save left behind, on top of whatever else changed the file.
+    fn save_todos(&mut self) {
+        let lock = self.storage.lock();
+        if lock.is_ok() {
+            self.load_todos();
+        }
+        let result = lock.and_then(|lock| self.storage.save(&lock, &self.todos));
+        self.finish_save(result);
+    }
+    
+    fn finish_save(&mut self, result: std::io::Result<Option<String>>) {
+        match result {
+            Ok(warning) => {
+                self.dirty = false;
+                self.saved = self.todos.clone();
+                self.unsaved.clear();
+                self.storage_error = None;
+                if warning.is_some() {
+                    self.storage_warning = warning;
                 }
             }
+            Err(e) => {
+                self.storage_error = Some(format!("Couldn't save {}: {}", self.storage.path().display(), e));
+            }
         }
     }
     
-    fn save_todos(&self) {
-        let mut content = String::new();
-        for todo in &self.todos {
-            let prefix = if todo.done { "[x]" } else { "[ ]" };
-            let display_text = if let Some(ref project) = todo.project {
-                format!("{}: {}", project, todo.text)
-            } else {
-                todo.text.clone()
-            };
-            content.push_str(&format!("{} {}\n", prefix, display_text));
+    /// Applies `change` as a locked read-modify-write: the file is reloaded
+    /// under the lock first, so tasks written by the CLI since the last
+    /// reload are not overwritten by our save.
+    fn modify_todos(&mut self, change: impl FnOnce(&mut Self)) {
+        let lock = self.storage.lock();
+        if lock.is_ok() {
+            self.load_todos();
         }
-        let _ = fs::write(&self.storage_path, content);
+        change(self);
+        let edits = std::mem::take(&mut self.pending_edits);
+        self.unsaved.extend(edits.iter().cloned());
+        self.history.record(edits);
+        self.dirty = true;
+        let result = lock.and_then(|lock| self.storage.save(&lock, &self.todos));
+        self.finish_save(result);
+    }
+    
+    /// Changes task `idx` in place, recording the change for undo.
+    fn update_todo(&mut self, idx: usize, change: impl FnOnce(&mut Todo)) {
+        let before = self.todos[idx].clone();
+        change(&mut self.todos[idx]);
+        let after = self.todos[idx].clone();
+        self.pending_edits.push(Edit::Replace { idx, before, after });
     }
     
+    fn push_todo(&mut self, todo: Todo) {
+        self.todos.push(todo);
+        let idx = self.todos.len() - 1;
+        self.pending_edits.push(Edit::Insert { idx, todo: self.todos[idx].clone() });
+    }
+    
+    fn remove_todo(&mut self, idx: usize) {
+        let todo = self.todos.remove(idx);
+        self.pending_edits.push(Edit::Remove { idx, todo });
+    }
+    
+    /// Completes or reopens task `idx`, recording the change and any
+    /// recurring task it spawned for undo.
+    fn set_todo_done(&mut self, idx: usize, done: bool) {
+        let before = self.todos[idx].clone();
+        let next = self.todos.set_done(idx, done);
+        let after = self.todos[idx].clone();
+        self.pending_edits.push(Edit::Replace { idx, before, after });
+        if let Some(next) = next {
+            self.pending_edits.push(Edit::Insert { idx: next, todo: self.todos[next].clone() });
+        }
+    }
+    
+    fn move_todo(&mut self, from: usize, to: usize) {
+        self.todos.move_task(from, to);
+        self.pending_edits.push(Edit::Move { from, to });
+    }
+    
+    /// Finds `todo` again after a reload, preferring its previous position.
+    fn locate_todo(&self, hint: usize, todo: &Todo) -> Option<usize> {
+        history::locate(&self.todos, hint, todo)
+    }
+    
+    /// Visible tasks with their index in the file, highest priority first
+    /// and in file order otherwise.
     fn filtered_todos(&self) -> Vec<(usize, &Todo)> {
-        self.todos
+        let shown = if self.filter == Filter::Archived { &self.archive } else { &self.todos };
+        let today = today();
+        let mut filtered: Vec<_> = shown
             .iter()
             .enumerate()
             .filter(|(_, todo)| {
-                let matches_filter = match self.filter {
-                    Filter::All => true,
-                    Filter::Active => !todo.done,
-                    Filter::Done => todo.done,
-                };
-                
-                let matches_project = match &self.project_filter {
-                    ProjectFilter::All => true,
-                    ProjectFilter::NoProject => todo.project.is_none(),
-                    ProjectFilter::Project(project) => {
-                        todo.project.as_ref() == Some(project)
-                    },
-                };
-                
-                let matches_search = if self.search.is_empty() {
-                    true
-                } else {
-                    let search_lower = self.search.to_lowercase();
-                    todo.text.to_lowercase().contains(&search_lower) ||
-                    todo.project.as_ref().map_or(false, |p| p.to_lowercase().contains(&search_lower))
-                };
-                
-                matches_filter && matches_project && matches_search
+                self.filter.matches(todo, today)
+                    && self.project_filter.matches(todo)
+                    && Self::matches_search(todo, &self.search)
             })
-            .collect()
+            .collect();
+        if self.sort_by_priority {
+            filtered.sort_by_key(|(_, todo)| todo.priority_rank());
+        }
+        filtered
     }
     
-    fn get_all_projects(&self) -> Vec<String> {
-        let mut projects: Vec<String> = self.todos
+    /// First and last filtered position covered by the visual selection, or
+    /// the cursor alone outside visual mode.
+    fn selection_range(&self) -> (usize, usize) {
+        match self.visual_anchor {
+            Some(anchor) => (anchor.min(self.selected), anchor.max(self.selected)),
+            None => (self.selected, self.selected),
+        }
+    }
+    
+    /// Filtered position a motion from the cursor ends at.
+    fn motion_target(&self, motion: Motion, count: Option<usize>) -> usize {
+        let last = self.filtered_todos().len().saturating_sub(1);
+        let n = count.unwrap_or(1);
+        let target = match motion {
+            Motion::Down => self.selected.saturating_add(n),
+            Motion::Up => self.selected.saturating_sub(n),
+            // With a count, `gg` and `G` go to that line
+            Motion::Top => count.map_or(0, |line| line.saturating_sub(1)),
+            Motion::Bottom => count.map_or(last, |line| line.saturating_sub(1)),
+            Motion::Lines => self.selected.saturating_add(n.saturating_sub(1)),
+        };
+        target.min(last)
+    }
+    
+    /// Filtered positions a command applies to: the visual selection, or
+    /// from the cursor to where its motion ends (`count` tasks by default).
+    fn command_range(&self, command: &Command) -> (usize, usize) {
+        if self.visual_anchor.is_some() {
+            return self.selection_range();
+        }
+        let target = self.motion_target(command.motion.unwrap_or(Motion::Lines), command.count);
+        (self.selected.min(target), self.selected.max(target))
+    }
+    
+    /// File indices of the tasks at filtered positions `start..=end`, in file order.
+    fn indices_in(&self, (start, end): (usize, usize)) -> Vec<usize> {
+        let mut indices: Vec<usize> = self.filtered_todos()
             .iter()
-            .filter_map(|todo| todo.project.clone())
-            .collect::<std::collections::HashSet<_>>()
-            .into_iter()
+            .skip(start)
+            .take(end + 1 - start)
+            .map(|(idx, _)| *idx)
             .collect();
-        projects.sort();
+        indices.sort_unstable();
+        indices
+    }
+    
+    /// Applies `change` to the tasks in `range` as one undoable step and
+    /// keeps the first of them under the cursor.
+    fn update_range(&mut self, range: (usize, usize), change: impl Fn(&mut Todo)) {
+        self.modify_todos(|app| {
+            let indices = app.indices_in(range);
+            for &idx in &indices {
+                app.update_todo(idx, &change);
+            }
+            if let Some(&first) = indices.first() {
+                app.select_todo(first);
+            }
+        });
+    }
+    
+    /// Moves the selection to the task at `real_idx`, e.g. after sorting moved it.
+    fn select_todo(&mut self, real_idx: usize) {
+        if let Some(pos) = self.filtered_todos().iter().position(|(i, _)| *i == real_idx) {
+            self.selected = pos;
+        }
+    }
+    
+    fn matches_search(todo: &Todo, search: &str) -> bool {
+        if search.is_empty() {
+            return true;
+        }
+        // Matches project, @contexts and tags as well as the text
+        todo.full_text().to_lowercase().contains(&search.to_lowercase())
+    }
+    
+    /// Projects with tasks and their parents, in tree order: each project is
+    /// followed by its subprojects, and pinned projects come first among
+    /// their siblings.
+    fn get_all_projects(&self) -> Vec<String> {
+        let mut projects = std::collections::HashSet::new();
+        for project in self.todos.iter().filter_map(|todo| todo.project.as_deref()) {
+            // Parents are listed even without tasks of their own
+            for (end, _) in project.match_indices(PROJECT_SEPARATOR) {
+                projects.insert(project[..end].to_string());
+            }
+            projects.insert(project.to_string());
+        }
+        
+        let mut projects: Vec<String> = projects.into_iter().collect();
+        projects.sort_by_cached_key(|project| {
+            let mut key = Vec::new();
+            let mut end = 0;
+            for segment in project.split(PROJECT_SEPARATOR) {
+                end += segment.len();
+                let pin = self.pinned_projects.iter().position(|p| *p == project[..end]);
+                key.push((pin.is_none(), pin, segment.to_string()));
+                end += PROJECT_SEPARATOR.len_utf8();
+            }
+            key
+        });
         projects
     }
     
+    /// Moves every task of project `from` and its subprojects to `to`, which
+    /// merges the two if `to` already exists. Pins and the project filter
+    /// follow along.
+    fn rename_project(&mut self, from: &str, to: &str) {
+        self.modify_todos(|app| {
+            for idx in 0..app.todos.len() {
+                let moved = app.todos[idx].project.as_deref().and_then(|project| move_project(project, from, to));
+                if let Some(moved) = moved {
+                    app.update_todo(idx, |todo| todo.project = Some(moved));
+                }
+            }
+            app.move_pins(from, Some(to));
+        });
+        if let ProjectFilter::Project(ref filter) = self.project_filter {
+            if let Some(moved) = move_project(filter, from, to) {
+                self.project_filter = ProjectFilter::Project(moved);
+            }
+        }
+    }
+    
+    /// Deletes every task of `project` and its subprojects.
+    fn delete_project(&mut self, project: &str) {
+        self.modify_todos(|app| {
+            for idx in (0..app.todos.len()).rev() {
+                if app.todos[idx].project.as_deref().is_some_and(|p| project_contains(project, p)) {
+                    app.remove_todo(idx);
+                }
+            }
+            app.move_pins(project, None);
+        });
+        if matches!(self.project_filter, ProjectFilter::Project(ref filter) if project_contains(project, filter)) {
+            self.project_filter = ProjectFilter::All;
+        }
+        self.selected = 0;
+    }
+    
+    /// Pins follow a renamed or deleted project, recorded for undo.
+    fn move_pins(&mut self, from: &str, to: Option<&str>) {
+        let before = self.pinned_projects.clone();
+        let after = lists::moved_pins(&before, from, to);
+        if after != before {
+            self.pending_edits.push(Edit::Pins { before, after: after.clone() });
+            self.pinned_projects = after;
+            self.save_pins();
+        }
+    }
+    
+    fn save_pins(&mut self) {
+        if let Err(e) = lists::set_pinned_projects(&self.storage, &self.pinned_projects) {
+            self.storage_warning = Some(format!("Couldn't save pinned projects: {}", e));
+        }
+    }
+    
+    fn toggle_pin(&mut self, project: &str) {
+        let mut pinned = self.pinned_projects.clone();
+        match pinned.iter().position(|p| p == project) {
+            Some(pos) => {
+                pinned.remove(pos);
+            }
+            None => pinned.push(project.to_string()),
+        }
+        match lists::set_pinned_projects(&self.storage, &pinned) {
+            Ok(()) => self.pinned_projects = pinned,
+            Err(e) => self.storage_warning = Some(format!("Couldn't save pinned projects: {}", e)),
+        }
+    }
+    
     fn get_project_task_count(&self, project: Option<&String>) -> (usize, usize) {
         let todos_for_project: Vec<_> = self.todos
             .iter()
             .filter(|todo| {
                 match project {
-                    Some(p) => todo.project.as_ref() == Some(p),
+                    Some(p) => todo.project.as_deref().is_some_and(|project| project_contains(p, project)),
                     None => todo.project.is_none(),
                 }
             })
@@ -470,7 +1220,28 @@ impl TodoApp {
         (active, total)
     }
     
+    /// A `[projects]` setting for `project` or, failing that, for its closest
+    /// ancestor that has it.
+    fn project_style<'a, T>(&'a self, project: &str, field: impl Fn(&'a ProjectStyle) -> Option<T>) -> Option<T> {
+        let mut name = project;
+        loop {
+            if let Some(value) = self.config.projects.get(name).and_then(&field) {
+                return Some(value);
+            }
+            name = &name[..name.rfind(PROJECT_SEPARATOR)?];
+        }
+    }
+    
+    fn get_project_glyph(&self, project: &str) -> Option<&str> {
+        self.project_style(project, |style| style.glyph.as_deref())
+    }
+    
     fn get_project_color(&self, project: &str) -> egui::Color32 {
+        let configured = self.project_style(project, |style| style.color.as_deref());
+        if let Some(color) = configured.and_then(|color| self.theme.named_color(color)) {
+            return color;
+        }
+        
         // Use the most contrasting colors from the current theme
         // Prioritize accent and bright colors that contrast well with foreground text
         let mut project_colors = Vec::new();
@@ -479,32 +1250,39 @@ impl TodoApp {
         project_colors.push(self.theme.accent);
         
         // Add theme colors that contrast well with foreground text
-        if let Some(red) = self.theme.red {
+        if let Some(red) = self.theme.normal.red {
             project_colors.push(red);
         }
-        if let Some(green) = self.theme.green {
+        if let Some(green) = self.theme.normal.green {
             project_colors.push(green);
         }
-        if let Some(yellow) = self.theme.yellow {
+        if let Some(yellow) = self.theme.normal.yellow {
             project_colors.push(yellow);
         }
-        if let Some(blue) = self.theme.blue {
+        if let Some(blue) = self.theme.normal.blue {
             // Only add blue if it's different from accent (since accent often uses blue)
             if blue != self.theme.accent {
                 project_colors.push(blue);
             }
         }
-        if let Some(magenta) = self.theme.magenta {
+        if let Some(magenta) = self.theme.normal.magenta {
             project_colors.push(magenta);
         }
-        if let Some(cyan) = self.theme.cyan {
+        if let Some(cyan) = self.theme.normal.cyan {
             // Only add cyan if it's different from done_color
             if cyan != self.theme.done_color {
                 project_colors.push(cyan);
             }
         }
         
-        // If we don't have enough colors, create brighter variants of existing ones
+        // The theme's bright colors, where they aren't just the normal ones again
+        for bright in self.theme.bright.colors()[1..7].iter().flatten() {
+            if !project_colors.contains(bright) {
+                project_colors.push(*bright);
+            }
+        }
+        
+        // If we still don't have enough colors, create brighter variants of existing ones
         if project_colors.len() < 4 {
             let brightened_accent = egui::Color32::from_rgb(
                 (self.theme.accent.r() as u16 * 120 / 100).min(255) as u8,
@@ -536,7 +1314,17 @@ impl TodoApp {
             return;
         }
         
-        egui::Window::new("Project Palette")
+        let assign = self.project_palette_assign;
+        let op = self.project_palette_op.clone();
+        let title = match (&op, assign) {
+            (Some(ProjectOp::Rename(_)), _) => "Rename Project",
+            (Some(ProjectOp::Merge(_)), _) => "Merge Project",
+            (Some(ProjectOp::Delete(_)), _) => "Delete Project",
+            (None, true) => "Move to Project",
+            (None, false) => "Project Palette",
+        };
+        egui::Window::new(title)
+            .id(egui::Id::new("project_palette"))
             .collapsible(false)
             .resizable(false)
             .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
@@ -544,33 +1332,73 @@ impl TodoApp {
                 ui.vertical(|ui| {
                     ui.set_min_width(300.0);
                     
-                    // Search input
-                    ui.horizontal(|ui| {
-                        ui.label("🔍");
-                        let response = ui.add(
-                            egui::TextEdit::singleline(&mut self.project_palette_search)
-                                .hint_text("Filter projects...")
-                                .desired_width(ui.available_width() - 30.0)
-                        );
-                        response.request_focus();
-                    });
+                    match &op {
+                        Some(ProjectOp::Rename(project)) => {
+                            ui.label(format!("New name for {}:", project));
+                        }
+                        Some(ProjectOp::Merge(project)) => {
+                            ui.label(format!("Move every task of {} to:", project));
+                        }
+                        Some(ProjectOp::Delete(project)) => {
+                            let (_, total) = self.get_project_task_count(Some(project));
+                            ui.label(format!("Delete {} and all {} of its tasks?", project, total));
+                        }
+                        None => {}
+                    }
+                    
+                    // Search input, which is the new name when renaming
+                    if !matches!(op, Some(ProjectOp::Delete(_))) {
+                        let renaming = matches!(op, Some(ProjectOp::Rename(_)));
+                        ui.horizontal(|ui| {
+                            ui.label(if renaming { "✏️" } else { "🔍" });
+                            let response = ui.add(
+                                egui::TextEdit::singleline(&mut self.project_palette_search)
+                                    .hint_text(if renaming { "New name..." } else { "Filter projects..." })
+                                    .desired_width(ui.available_width() - 30.0)
+                            );
+                            response.request_focus();
+                        });
+                        let name = self.project_palette_search.trim();
+                        if let Some(ProjectOp::Rename(ref project)) = op {
+                            if !name.is_empty() && name != project {
+                                if let Err(e) = check_project_move(&self.todos, project, name, false) {
+                                    ui.label(egui::RichText::new(e)
+                                        .color(self.theme.done_color)
+                                        .small());
+                                }
+                            }
+                        }
+                    }
                     
                     ui.separator();
                     
                     // Build list of project options
-                    let mut options = vec![("All".to_string(), None, self.todos.len())];
-                    
-                    let (_no_project_active, no_project_total) = self.get_project_task_count(None);
-                    options.push(("No project".to_string(), Some(None), no_project_total));
-                    
-                    let projects = self.get_all_projects();
-                    for project in projects {
-                        let (_active_count, total_count) = self.get_project_task_count(Some(&project));
-                        options.push((project.clone(), Some(Some(project)), total_count));
+                    let mut options = Vec::new();
+                    if op.is_none() {
+                        if !assign {
+                            options.push(("All".to_string(), None, self.todos.len()));
+                        }
+                        
+                        let (_no_project_active, no_project_total) = self.get_project_task_count(None);
+                        options.push(("No project".to_string(), Some(None), no_project_total));
+                    }
+                    // Merging picks another project; renaming and deleting need no list
+                    if matches!(op, None | Some(ProjectOp::Merge(_))) {
+                        let source = match op {
+                            Some(ProjectOp::Merge(ref source)) => Some(source),
+                            _ => None,
+                        };
+                        for project in self.get_all_projects() {
+                            // A project can't be merged into itself or its subprojects
+                            if !source.is_some_and(|source| project_contains(source, &project)) {
+                                let (_active_count, total_count) = self.get_project_task_count(Some(&project));
+                                options.push((project.clone(), Some(Some(project)), total_count));
+                            }
+                        }
                     }
                     
                     // Filter options based on search
-                    let filtered_options: Vec<_> = options
+                    let mut filtered_options: Vec<_> = options
                         .into_iter()
                         .filter(|(name, _, _)| {
                             if self.project_palette_search.is_empty() {
@@ -581,54 +1409,125 @@ impl TodoApp {
                         })
                         .collect();
                     
+                    if assign {
+                        // Typing a name that doesn't exist yet creates that project
+                        let typed = self.project_palette_search.trim().to_string();
+                        if is_valid_project(&typed) && !self.get_all_projects().contains(&typed) {
+                            filtered_options.insert(0, (format!("New project: {}", typed), Some(Some(typed)), 0));
+                        }
+                    }
+                    
                     // Adjust selection if it's out of bounds
                     if self.project_palette_selected >= filtered_options.len() {
                         self.project_palette_selected = filtered_options.len().saturating_sub(1);
                     }
+                    let selected_project = filtered_options
+                        .get(self.project_palette_selected)
+                        .and_then(|(_, filter_option, _)| filter_option.clone().flatten());
                     
                     // Handle keyboard input
-                    ctx.input(|i| {
-                        for event in &i.events {
-                            if let egui::Event::Key { key, pressed: true, .. } = event {
-                                match key {
-                                    egui::Key::ArrowDown | egui::Key::J => {
-                                        if self.project_palette_selected < filtered_options.len().saturating_sub(1) {
-                                            self.project_palette_selected += 1;
+                    let keys: Vec<(egui::Key, egui::Modifiers)> = ctx.input(|i| {
+                        i.events
+                            .iter()
+                            .filter_map(|event| match event {
+                                egui::Event::Key { key, pressed: true, modifiers, .. } => Some((*key, *modifiers)),
+                                _ => None,
+                            })
+                            .collect()
+                    });
+                    for (key, modifiers) in keys {
+                        match key {
+                            egui::Key::ArrowDown | egui::Key::J if !modifiers.ctrl => {
+                                self.project_palette_selected = (self.project_palette_selected + 1)
+                                    .min(filtered_options.len().saturating_sub(1));
+                            }
+                            egui::Key::ArrowUp | egui::Key::K if !modifiers.ctrl => {
+                                self.project_palette_selected = self.project_palette_selected.saturating_sub(1);
+                            }
+                            egui::Key::Enter => {
+                                match op {
+                                    Some(ProjectOp::Rename(ref project)) => {
+                                        let name = self.project_palette_search.trim().to_string();
+                                        if name != *project {
+                                            if check_project_move(&self.todos, project, &name, false).is_err() {
+                                                continue;
+                                            }
+                                            self.rename_project(project, &name);
                                         }
                                     }
-                                    egui::Key::ArrowUp | egui::Key::K => {
-                                        if self.project_palette_selected > 0 {
-                                            self.project_palette_selected -= 1;
+                                    Some(ProjectOp::Merge(ref project)) => {
+                                        let Some(ref target) = selected_project else { continue };
+                                        if check_project_move(&self.todos, project, target, true).is_err() {
+                                            continue;
                                         }
+                                        self.rename_project(project, target);
                                     }
-                                    egui::Key::Enter => {
-                                        if let Some((_, filter_option, _)) = filtered_options.get(self.project_palette_selected) {
+                                    Some(ProjectOp::Delete(ref project)) => self.delete_project(project),
+                                    None => {
+                                        let Some((_, filter_option, _)) = filtered_options.get(self.project_palette_selected) else { continue };
+                                        if assign {
+                                            let project = filter_option.clone().flatten();
+                                            self.update_range(self.selection_range(), |todo| todo.project = project.clone());
+                                            self.visual_anchor = None;
+                                        } else {
                                             match filter_option {
                                                 None => self.project_filter = ProjectFilter::All,
                                                 Some(None) => self.project_filter = ProjectFilter::NoProject,
                                                 Some(Some(project)) => self.project_filter = ProjectFilter::Project(project.clone()),
                                             }
-                                            self.show_project_palette = false;
                                             self.selected = 0;
                                         }
                                     }
-                                    egui::Key::Escape => {
-                                        self.show_project_palette = false;
+                                }
+                                self.show_project_palette = false;
+                                self.project_palette_op = None;
+                                break;
+                            }
+                            egui::Key::Escape => {
+                                if op.is_some() {
+                                    // Back to the list
+                                    self.project_palette_op = None;
+                                    self.project_palette_search.clear();
+                                } else {
+                                    self.show_project_palette = false;
+                                    self.visual_anchor = None;
+                                }
+                                break;
+                            }
+                            // Project actions, on the selected project
+                            egui::Key::R | egui::Key::M | egui::Key::D | egui::Key::E | egui::Key::P
+                                if modifiers.ctrl && op.is_none() && !assign =>
+                            {
+                                let Some(project) = selected_project.clone() else { continue };
+                                match key {
+                                    egui::Key::R => {
+                                        self.project_palette_search = project.clone();
+                                        self.project_palette_op = Some(ProjectOp::Rename(project));
+                                    }
+                                    egui::Key::M => {
+                                        self.project_palette_search.clear();
+                                        self.project_palette_selected = 0;
+                                        self.project_palette_op = Some(ProjectOp::Merge(project));
                                     }
-                                    _ => {}
+                                    egui::Key::D => self.project_palette_op = Some(ProjectOp::Delete(project)),
+                                    egui::Key::E => self.archive_tasks(|todo| todo.done && todo.project.as_deref().is_some_and(|p| project_contains(&project, p))),
+                                    _ => self.toggle_pin(&project),
                                 }
+                                break;
                             }
+                            _ => {}
                         }
-                    });
+                    }
                     
-                    // Render options
+                    // Render options, as a tree unless filtered
+                    let tree = self.project_palette_search.is_empty();
                     egui::ScrollArea::vertical()
                         .max_height(200.0)
                         .show(ui, |ui| {
-                            for (i, (name, filter_option, _total_count)) in filtered_options.iter().enumerate() {
+                            for (i, (name, filter_option, total_count)) in filtered_options.iter().enumerate() {
                                 let is_selected = i == self.project_palette_selected;
                                 let bg_color = if is_selected {
-                                    self.theme.accent.gamma_multiply(0.3)
+                                    self.theme.selection()
                                 } else {
                                     egui::Color32::TRANSPARENT
                                 };
@@ -640,8 +1539,29 @@ impl TodoApp {
                                 frame.show(ui, |ui| {
                                     ui.horizontal(|ui| {
                                         ui.label(if is_selected { "▶" } else { " " });
-                                        ui.label(name);
+                                        let pinned = matches!(filter_option, Some(Some(project)) if self.pinned_projects.contains(project));
+                                        if pinned {
+                                            ui.label(egui::RichText::new("★").color(self.theme.accent));
+                                        }
+                                        match filter_option {
+                                            Some(Some(project)) => {
+                                                let (indent, label) = if tree {
+                                                    let depth = project.matches(PROJECT_SEPARATOR).count();
+                                                    let leaf = project.rsplit(PROJECT_SEPARATOR).next().unwrap_or(project);
+                                                    ("    ".repeat(depth), leaf)
+                                                } else {
+                                                    (String::new(), name.as_str())
+                                                };
+                                                let glyph = self.get_project_glyph(project).map(|glyph| format!("{} ", glyph)).unwrap_or_default();
+                                                ui.label(egui::RichText::new(format!("{}{}{}", indent, glyph, label))
+                                                    .color(self.get_project_color(project)));
+                                            }
+                                            _ => {
+                                                ui.label(name);
+                                            }
+                                        }
                                         ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                            // Counts include subprojects
                                             let active_count = match filter_option {
                                                 None => {
                                                     let active = self.todos.iter().filter(|t| !t.done).count();
@@ -656,7 +1576,7 @@ impl TodoApp {
                                                     active
                                                 },
                                             };
-                                            ui.label(format!("{} open", active_count));
+                                            ui.label(format!("{} open / {}", active_count, total_count));
                                         });
                                     });
                                 });
@@ -664,117 +1584,246 @@ impl TodoApp {
                         });
                     
                     ui.separator();
+                    let help = match (&op, assign) {
+                        (Some(ProjectOp::Rename(_)), _) => "Enter: Rename | Esc: Back",
+                        (Some(ProjectOp::Merge(_)), _) => "j/k: Move | Enter: Merge | Esc: Back",
+                        (Some(ProjectOp::Delete(_)), _) => "Enter: Delete | Esc: Back",
+                        (None, true) => "j/k: Move | Enter: Move tasks here | Esc: Cancel",
+                        (None, false) => "j/k: Move | Enter: Select | Esc: Cancel\nCtrl+R: Rename | Ctrl+M: Merge | Ctrl+D: Delete | Ctrl+E: Archive done | Ctrl+P: Pin",
+                    };
+                    ui.label(egui::RichText::new(help)
+                        .color(self.theme.done_color)
+                        .size(10.0));
+                });
+            });
+    }
+    
+    fn render_list_palette(&mut self, ctx: &egui::Context) {
+        if !self.show_list_palette {
+            return;
+        }
+        
+        let current = lists::name_of(self.storage.path());
+        egui::Window::new("Lists")
+            .collapsible(false)
+            .resizable(false)
+            .anchor(egui::Align2::CENTER_CENTER, egui::Vec2::ZERO)
+            .show(ctx, |ui| {
+                ui.vertical(|ui| {
+                    ui.set_min_width(300.0);
+                    
                     ui.horizontal(|ui| {
-                        ui.label(egui::RichText::new("j/k: Move | Enter: Select | Esc: Cancel")
-                            .color(self.theme.done_color)
-                            .size(10.0));
+                        ui.label("🔍");
+                        let response = ui.add(
+                            egui::TextEdit::singleline(&mut self.list_palette_search)
+                                .hint_text("Filter or create lists...")
+                                .desired_width(ui.available_width() - 30.0)
+                        );
+                        response.request_focus();
+                    });
+                    
+                    ui.separator();
+                    
+                    let search = self.list_palette_search.to_lowercase();
+                    let mut options: Vec<(String, String, Option<usize>)> = self.list_palette_lists
+                        .iter()
+                        .filter(|(name, _)| name.to_lowercase().contains(&search))
+                        .map(|(name, open)| (name.clone(), name.clone(), Some(*open)))
+                        .collect();
+                    
+                    // Typing a name that doesn't exist yet creates that list
+                    let typed = self.list_palette_search.trim();
+                    if lists::is_valid_name(typed) && !self.list_palette_lists.iter().any(|(name, _)| name == typed) {
+                        options.insert(0, (format!("New list: {}", typed), typed.to_string(), None));
+                    }
+                    
+                    if self.list_palette_selected >= options.len() {
+                        self.list_palette_selected = options.len().saturating_sub(1);
+                    }
+                    
+                    let mut chosen = None;
+                    ctx.input(|i| {
+                        for event in &i.events {
+                            if let egui::Event::Key { key, pressed: true, .. } = event {
+                                match key {
+                                    egui::Key::ArrowDown | egui::Key::J => {
+                                        self.list_palette_selected = (self.list_palette_selected + 1)
+                                            .min(options.len().saturating_sub(1));
+                                    }
+                                    egui::Key::ArrowUp | egui::Key::K => {
+                                        self.list_palette_selected = self.list_palette_selected.saturating_sub(1);
+                                    }
+                                    egui::Key::Enter => {
+                                        if let Some((_, name, _)) = options.get(self.list_palette_selected) {
+                                            chosen = Some(name.clone());
+                                        }
+                                    }
+                                    egui::Key::Escape => self.show_list_palette = false,
+                                    _ => {}
+                                }
+                            }
+                        }
                     });
+                    
+                    egui::ScrollArea::vertical()
+                        .max_height(200.0)
+                        .show(ui, |ui| {
+                            for (i, (label, name, open)) in options.iter().enumerate() {
+                                let is_selected = i == self.list_palette_selected;
+                                let bg_color = if is_selected {
+                                    self.theme.selection()
+                                } else {
+                                    egui::Color32::TRANSPARENT
+                                };
+                                
+                                egui::Frame::none()
+                                    .fill(bg_color)
+                                    .inner_margin(egui::Margin::same(4.0))
+                                    .show(ui, |ui| {
+                                        ui.horizontal(|ui| {
+                                            ui.label(if is_selected { "▶" } else { " " });
+                                            if current.as_ref() == Some(name) {
+                                                ui.label(egui::RichText::new(label).color(self.theme.accent));
+                                            } else {
+                                                ui.label(label);
+                                            }
+                                            if let Some(open) = open {
+                                                ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                                                    ui.label(format!("{} open", open));
+                                                });
+                                            }
+                                        });
+                                    });
+                            }
+                        });
+                    
+                    ui.separator();
+                    ui.label(egui::RichText::new("j/k: Move | Enter: Switch | Esc: Cancel")
+                        .color(self.theme.done_color)
+                        .size(10.0));
+                    
+                    if let Some(name) = chosen {
+                        self.show_list_palette = false;
+                        if current.as_deref() != Some(name.as_str()) {
+                            self.switch_list(&name);
+                        }
+                    }
                 });
             });
     }
     
     fn handle_keyboard(&mut self, ctx: &egui::Context) {
-        let mut actions = Vec::new();
-        
+        let mut keys = Vec::new();
         ctx.input(|i| {
             for event in &i.events {
                 if let egui::Event::Key { key, pressed: true, modifiers, .. } = event {
-                    // Skip main keyboard handling if project palette is open
-                    // Allow Escape key through even if search is open
-                    if self.show_project_palette || (self.show_search && !matches!(key, egui::Key::Escape)) {
-                        continue;
-                    }
-                    
-                    if self.editing.is_some() {
-                        match key {
-                            egui::Key::Enter => actions.push(KeyAction::SaveEdit),
-                            egui::Key::Escape => actions.push(KeyAction::CancelEdit),
-                            _ => {}
-                        }
-                        continue;
-                    }
-                    
-                    match key {
-                        egui::Key::J | egui::Key::ArrowDown => actions.push(KeyAction::MoveDown),
-                        egui::Key::K | egui::Key::ArrowUp => actions.push(KeyAction::MoveUp),
-                        egui::Key::G => {
-                            if modifiers.shift {
-                                actions.push(KeyAction::GoToBottom);
-                            } else {
-                                actions.push(KeyAction::GoToTop);
-                            }
-                        }
-                        egui::Key::P => {
-                            if modifiers.shift {
-                                actions.push(KeyAction::OpenProjectPalette);
-                            } else {
-                                actions.push(KeyAction::CycleProject);
-                            }
-                        }
-                        egui::Key::S => {
-                            if modifiers.shift {
-                                actions.push(KeyAction::ToggleSearch);
-                            } else {
-                                actions.push(KeyAction::ClearDelete);
-                            }
-                        }
-                        egui::Key::Enter => actions.push(KeyAction::EditSelected),
-                        egui::Key::A => actions.push(KeyAction::AddNew),
-                        egui::Key::X => actions.push(KeyAction::ToggleSelected),
-                        egui::Key::D => actions.push(KeyAction::DeleteKey),
-                        egui::Key::F => actions.push(KeyAction::CycleFilter),
-                        egui::Key::C => actions.push(KeyAction::ClearAllFilters),
-                        egui::Key::Plus | egui::Key::Equals => {
-                            if modifiers.ctrl {
-                                actions.push(KeyAction::IncreaseFontSize);
-                            } else {
-                                actions.push(KeyAction::ClearDelete);
-                            }
-                        }
-                        egui::Key::Minus => {
-                            if modifiers.ctrl {
-                                actions.push(KeyAction::DecreaseFontSize);
-                            } else {
-                                actions.push(KeyAction::ClearDelete);
-                            }
-                        }
-                        egui::Key::Num0 => {
-                            if modifiers.ctrl {
-                                actions.push(KeyAction::ResetFontSize);
-                            } else {
-                                actions.push(KeyAction::ClearDelete);
-                            }
-                        }
-                        egui::Key::Slash => {}, // Handle search focus separately to avoid conflicts
-                        egui::Key::Escape => actions.push(KeyAction::ClearSearch),
-                        _ => actions.push(KeyAction::ClearDelete),
-                    }
+                    keys.push((*key, *modifiers));
                 }
             }
         });
         
-        for action in actions {
-            self.handle_key_action(action);
+        for (key, modifiers) in keys {
+            // Skip main keyboard handling if project palette is open
+            // Allow Escape key through even if search is open
+            if self.show_project_palette || self.show_list_palette || (self.show_search && key != egui::Key::Escape) {
+                continue;
+            }
+            
+            if self.editing.is_some() {
+                match key {
+                    egui::Key::Enter => self.handle_command(Command::new(KeyAction::SaveEdit)),
+                    egui::Key::Escape => self.handle_command(Command::new(KeyAction::CancelEdit)),
+                    _ => {}
+                }
+                continue;
+            }
+            
+            // Plain digits build up a count
+            if !modifiers.any() {
+                if let Some(digit) = key.name().parse::<u32>().ok().filter(|d| *d < 10) {
+                    if self.key_parser.digit(digit) {
+                        continue;
+                    }
+                }
+            }
+            if key == egui::Key::Escape && !self.key_parser.pending().is_empty() {
+                self.key_parser.reset();
+                continue;
+            }
+            
+            // Unbound keys cancel a pending command, as in vim
+            let Some(action) = self.keymap.action(key, modifiers) else {
+                self.key_parser.reset();
+                continue;
+            };
+            let label = if modifiers.shift { key.name().to_string() } else { key.name().to_lowercase() };
+            if let Some(command) = self.key_parser.feed(action, &label, self.visual_anchor.is_some()) {
+                self.handle_command(command);
+            }
         }
     }
     
-    fn handle_key_action(&mut self, action: KeyAction) {
+    fn handle_command(&mut self, command: Command) {
+        let Command { action, count, .. } = command;
+        // As in vim, anything but moving the cursor ends visual mode
+        let keeps_visual = matches!(
+            action,
+            KeyAction::MoveDown
+                | KeyAction::MoveUp
+                | KeyAction::GoToBottom
+                | KeyAction::GoToTop
+                | KeyAction::ToggleVisual
+                | KeyAction::MoveToProject
+        );
+        let is_change = matches!(
+            action,
+            KeyAction::ToggleSelected
+                | KeyAction::Delete
+                | KeyAction::RaisePriority
+                | KeyAction::LowerPriority
+                | KeyAction::SetPriority(_)
+                | KeyAction::MoveTaskDown
+                | KeyAction::MoveTaskUp
+        );
+        // The archive can only be read and restored from
+        let edits_list = is_change
+            || matches!(
+                action,
+                KeyAction::EditSelected
+                    | KeyAction::AddNew
+                    | KeyAction::MoveToProject
+                    | KeyAction::Undo
+                    | KeyAction::Redo
+                    | KeyAction::Repeat
+            );
+        if edits_list && self.filter == Filter::Archived {
+            return;
+        }
+        if is_change {
+            // A change made in visual mode repeats on as many tasks
+            self.last_change = Some(match self.visual_anchor {
+                Some(_) => {
+                    let (start, end) = self.selection_range();
+                    Command { count: Some(end - start + 1), ..command }
+                }
+                None => command,
+            });
+        }
+        let range = self.command_range(&command);
+        
         match action {
             KeyAction::SaveEdit => {
                 if let Some(idx) = self.editing {
                     if !self.edit_text.trim().is_empty() {
-                        let (text, project) = Self::parse_todo_text(self.edit_text.trim());
-                        if idx < self.todos.len() {
-                            self.todos[idx].text = text;
-                            self.todos[idx].project = project;
-                        } else {
-                            self.todos.push(Todo {
-                                text,
-                                done: false,
-                                project,
-                            });
-                        }
-                        self.save_todos();
+                        let input = self.edit_text.trim().to_string();
+                        let original = self.todos.get(idx).cloned();
+                        self.modify_todos(|app| {
+                            // If the task vanished from the file meanwhile, keep the edit as a new task
+                            match original.and_then(|todo| app.locate_todo(idx, &todo)) {
+                                Some(idx) => app.update_todo(idx, |todo| todo.set_input(&input)),
+                                None => app.push_todo(Todo::from_input(&input)),
+                            }
+                        });
                     }
                     self.editing = None;
                     self.edit_text.clear();
@@ -784,35 +1833,16 @@ impl TodoApp {
                 self.editing = None;
                 self.edit_text.clear();
             }
-            KeyAction::MoveDown => {
-                let filtered = self.filtered_todos();
-                if !filtered.is_empty() {
-                    self.selected = (self.selected + 1).min(filtered.len() - 1);
-                }
-            }
-            KeyAction::MoveUp => {
-                if self.selected > 0 {
-                    self.selected -= 1;
+            KeyAction::MoveDown | KeyAction::MoveUp | KeyAction::GoToBottom | KeyAction::GoToTop => {
+                if let Some(motion) = Motion::of(action) {
+                    self.selected = self.motion_target(motion, count);
                 }
             }
-            KeyAction::GoToBottom => {
-                let filtered = self.filtered_todos();
-                if !filtered.is_empty() {
-                    self.selected = filtered.len() - 1;
-                }
-            }
-            KeyAction::GoToTop => {
-                self.selected = 0;
-            }
             KeyAction::EditSelected => {
                 let filtered = self.filtered_todos();
                 if let Some((real_idx, todo)) = filtered.get(self.selected) {
                     let real_idx = *real_idx;
-                    let text = if let Some(ref project) = todo.project {
-                        format!("{}: {}", project, todo.text)
-                    } else {
-                        todo.text.clone()
-                    };
+                    let text = todo.full_text();
                     self.editing = Some(real_idx);
                     self.edit_text = text;
                 }
@@ -826,46 +1856,82 @@ impl TodoApp {
                 };
             }
             KeyAction::ToggleSelected => {
-                let filtered = self.filtered_todos();
-                if let Some((real_idx, _)) = filtered.get(self.selected) {
-                    let real_idx = *real_idx;
-                    self.todos[real_idx].done = !self.todos[real_idx].done;
-                    self.save_todos();
-                }
-            }
-            KeyAction::DeleteKey => {
-                if self.delete_mode {
-                    let filtered = self.filtered_todos();
-                    if let Some((real_idx, _)) = filtered.get(self.selected) {
-                        let real_idx = *real_idx;
-                        let filtered_len = filtered.len();
-                        self.todos.remove(real_idx);
-                        if self.selected >= filtered_len - 1 && self.selected > 0 {
-                            self.selected -= 1;
+                self.modify_todos(|app| {
+                    // Complete the whole selection unless it's all done already
+                    let indices = app.indices_in(range);
+                    let done = !indices.iter().all(|&idx| app.todos[idx].done);
+                    for idx in indices {
+                        if app.todos[idx].done != done {
+                            app.set_todo_done(idx, done);
                         }
-                        self.save_todos();
                     }
-                    self.delete_mode = false;
-                } else {
-                    self.delete_mode = true;
-                }
+                });
+            }
+            KeyAction::Delete => {
+                self.modify_todos(|app| {
+                    for idx in app.indices_in(range).into_iter().rev() {
+                        app.remove_todo(idx);
+                    }
+                    let visible = app.filtered_todos().len();
+                    app.selected = range.0.min(visible.saturating_sub(1));
+                });
             }
             KeyAction::CycleFilter => {
                 self.filter = self.filter.next();
                 self.selected = 0;
+                if self.filter == Filter::Archived {
+                    self.load_archive();
+                }
             }
-            KeyAction::ClearSearch => {
-                self.search.clear();
-                self.show_search = false;
-                self.selected = 0;
+            KeyAction::Archive => self.archive_tasks(|todo| archive::is_archivable(todo, None)),
+            KeyAction::TogglePrioritySort => {
+                let current = self.filtered_todos().get(self.selected).map(|&(idx, _)| idx);
+                self.sort_by_priority = !self.sort_by_priority;
+                if let Some(idx) = current {
+                    self.select_todo(idx);
+                }
+            }
+            KeyAction::Restore => {
+                if self.filter == Filter::Archived {
+                    self.restore_archived(range);
+                }
             }
-            KeyAction::ClearDelete => {
-                self.delete_mode = false;
+            KeyAction::ClearSearch => {
+                // Escape leaves visual mode first
+                if self.visual_anchor.is_none() {
+                    self.search.clear();
+                    self.show_search = false;
+                    self.selected = 0;
+                }
             }
-            KeyAction::OpenProjectPalette => {
+            KeyAction::OpenProjectPalette | KeyAction::MoveToProject => {
                 self.show_project_palette = true;
+                self.project_palette_assign = matches!(action, KeyAction::MoveToProject);
                 self.project_palette_search.clear();
                 self.project_palette_selected = 0;
+                self.project_palette_op = None;
+            }
+            KeyAction::OpenListPalette => {
+                self.show_list_palette = true;
+                self.list_palette_search.clear();
+                self.list_palette_selected = 0;
+                let mut names = lists::names();
+                if let Some(current) = lists::name_of(self.storage.path()) {
+                    if !names.contains(&current) {
+                        names.push(current);
+                        names.sort();
+                    }
+                }
+                self.list_palette_lists = names
+                    .into_iter()
+                    .map(|name| {
+                        let open = Storage::new(lists::path(&name))
+                            .load()
+                            .map(|todos| todos.iter().filter(|todo| !todo.done).count())
+                            .unwrap_or(0);
+                        (name, open)
+                    })
+                    .collect();
             }
             KeyAction::ToggleSearch => {
                 self.show_search = !self.show_search;
@@ -912,27 +1978,226 @@ impl TodoApp {
                 self.selected = 0;
             }
             KeyAction::IncreaseFontSize => {
-                let current_size = self.user_font_size
-                    .or(self.theme.font_size)
-                    .unwrap_or(14.0);
-                self.user_font_size = Some((current_size + 1.0).min(24.0));
+                let current_size = self.get_effective_font_size();
+                self.user_font_size = Some((current_size + 1.0).min(self.config.font.max_size));
             }
             KeyAction::DecreaseFontSize => {
-                let current_size = self.user_font_size
-                    .or(self.theme.font_size)
-                    .unwrap_or(14.0);
-                self.user_font_size = Some((current_size - 1.0).max(8.0));
+                let current_size = self.get_effective_font_size();
+                self.user_font_size = Some((current_size - 1.0).max(self.config.font.min_size));
             }
             KeyAction::ResetFontSize => {
                 self.user_font_size = None;
             }
+            KeyAction::RetryStorage => {
+                if self.dirty {
+                    self.save_todos();
+                } else {
+                    self.load_todos();
+                }
+            }
+            KeyAction::RaisePriority | KeyAction::LowerPriority => {
+                let raise = matches!(action, KeyAction::RaisePriority);
+                self.update_range(range, |todo| todo.shift_priority(raise));
+            }
+            KeyAction::SetPriority(priority) => {
+                self.update_range(range, |todo| todo.priority = priority);
+            }
+            KeyAction::ToggleVisual => {
+                self.visual_anchor = match self.visual_anchor {
+                    Some(_) => None,
+                    None if self.filtered_todos().is_empty() => None,
+                    None => Some(self.selected),
+                };
+            }
+            KeyAction::Yank => {
+                let filtered = self.filtered_todos();
+                let (start, end) = range;
+                let lines: Vec<String> = filtered
+                    .iter()
+                    .skip(start)
+                    .take(end + 1 - start)
+                    .map(|(_, todo)| todo.full_text())
+                    .collect();
+                if !lines.is_empty() {
+                    self.clipboard = Some(lines.join("\n"));
+                }
+            }
+            KeyAction::MoveTaskDown | KeyAction::MoveTaskUp => {
+                let down = matches!(action, KeyAction::MoveTaskDown);
+                self.modify_todos(|app| {
+                    for _ in 0..count.unwrap_or(1) {
+                        let filtered = app.filtered_todos();
+                        let neighbor = if down { app.selected.checked_add(1) } else { app.selected.checked_sub(1) };
+                        let (Some(&(from, todo)), Some(&(to, other))) =
+                            (filtered.get(app.selected), neighbor.and_then(|n| filtered.get(n)))
+                        else {
+                            break;
+                        };
+                        // Taking the neighbor's place in the file moves the task past
+                        // it, and past any hidden tasks in between. While the list is
+                        // sorted by priority, only tasks of equal priority can trade
+                        // places; anything else would be undone by the sort.
+                        if app.sort_by_priority && todo.priority_rank() != other.priority_rank() {
+                            let toggle = match app.keymap.shortcut(KeyAction::TogglePrioritySort) {
+                                Some(key) => format!("press {}", key),
+                                None => "bind priority_sort".to_string(),
+                            };
+                            app.storage_warning = Some(format!(
+                                "Sorted by priority, tasks only trade places within a priority; {} to sort by file order",
+                                toggle
+                            ));
+                            break;
+                        }
+                        app.move_todo(from, to);
+                        app.select_todo(to);
+                    }
+                });
+            }
+            KeyAction::Undo | KeyAction::Redo => {
+                let undo = matches!(action, KeyAction::Undo);
+                let possible = if undo { self.history.can_undo() } else { self.history.can_redo() };
+                if possible {
+                    self.modify_todos(|app| {
+                        // The reload in modify_todos clears the history if the file changed
+                        let mut focus = None;
+                        let pins = app.pinned_projects.clone();
+                        for _ in 0..count.unwrap_or(1) {
+                            focus = if undo {
+                                app.history.undo(&mut app.todos, &mut app.pinned_projects, &mut app.unsaved)
+                            } else {
+                                app.history.redo(&mut app.todos, &mut app.pinned_projects, &mut app.unsaved)
+                            }
+                            .or(focus);
+                        }
+                        if app.pinned_projects != pins {
+                            app.save_pins();
+                        }
+                        let visible = app.filtered_todos().len();
+                        app.selected = app.selected.min(visible.saturating_sub(1));
+                        if let Some(idx) = focus {
+                            app.select_todo(idx);
+                        }
+                    });
+                }
+            }
+            KeyAction::Repeat => {
+                if let Some(mut last) = self.last_change {
+                    if count.is_some() {
+                        last.count = count;
+                    }
+                    self.handle_command(last);
+                }
+            }
+        }
+        
+        if !keeps_visual {
+            self.visual_anchor = None;
+        }
+    }
+    
+    fn apply_font_family(&mut self, ctx: &egui::Context) {
+        let mut fonts = egui::FontDefinitions::default();
+        
+        if let Some(ref font_family) = self.theme.font_family {
+            let face = match self.font_resolver.resolve(font_family) {
+                // Keep the current font until the lookup finishes and repaints
+                Lookup::Pending => return,
+                Lookup::Found(face) => Some(face),
+                Lookup::Missing => None,
+            };
+            if let Some(Ok(font_data)) = face.as_ref().map(|face| fs::read(&face.path)) {
+                let mut font_data = egui::FontData::from_owned(font_data);
+                font_data.index = face.map_or(0, |face| face.index);
+                fonts.font_data.insert(font_family.clone(), font_data);
+                fonts.families.entry(egui::FontFamily::Proportional).or_default()
+                    .insert(0, font_family.clone());
+                fonts.families.entry(egui::FontFamily::Monospace).or_default()
+                    .insert(0, font_family.clone());
+            }
         }
+        
+        ctx.set_fonts(fonts);
+        self.applied_font_family = self.theme.font_family.clone();
     }
     
     fn get_effective_font_size(&self) -> f32 {
         self.user_font_size
             .or(self.theme.font_size)
-            .unwrap_or(14.0)
+            .unwrap_or(self.config.font.size)
+    }
+    
+    /// Storage errors and problems with the config file.
+    fn render_banner(&mut self, ui: &mut egui::Ui) {
+        if self.storage_error.is_none()
+            && self.storage_warning.is_none()
+            && self.config_warning.is_none()
+            && self.theme_warning.is_none()
+        {
+            return;
+        }
+        
+        let error_color = self.theme.normal.red.unwrap_or(egui::Color32::from_rgb(255, 100, 100));
+        let retry_label = match self.keymap.shortcut(KeyAction::RetryStorage) {
+            Some(key) => format!("Retry ({})", key),
+            None => "Retry".to_string(),
+        };
+        let mut retry = false;
+        let mut dismiss_warning = false;
+        let mut dismiss_config_warning = false;
+        let mut dismiss_theme_warning = false;
+        
+        egui::Frame::none()
+            .fill(error_color.gamma_multiply(0.2))
+            .stroke(egui::Stroke::new(1.0, error_color))
+            .inner_margin(egui::Margin::same(6.0))
+            .show(ui, |ui| {
+                ui.set_width(ui.available_width());
+                if let Some(ref error) = self.storage_error {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(format!("⚠ {}", error)).color(error_color));
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            retry = ui.button(retry_label).clicked();
+                        });
+                    });
+                }
+                if let Some(ref warning) = self.storage_warning {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(format!("⚠ {}", warning)).color(error_color));
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            dismiss_warning = ui.button("Dismiss").clicked();
+                        });
+                    });
+                }
+                if let Some(ref warning) = self.config_warning {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(format!("⚠ {}", warning)).color(error_color));
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            dismiss_config_warning = ui.button("Dismiss").clicked();
+                        });
+                    });
+                }
+                if let Some(ref warning) = self.theme_warning {
+                    ui.horizontal(|ui| {
+                        ui.label(egui::RichText::new(format!("⚠ {}", warning)).color(error_color));
+                        ui.with_layout(egui::Layout::right_to_left(egui::Align::Center), |ui| {
+                            dismiss_theme_warning = ui.button("Dismiss").clicked();
+                        });
+                    });
+                }
+            });
+        
+        if retry {
+            self.handle_command(Command::new(KeyAction::RetryStorage));
+        }
+        if dismiss_warning {
+            self.storage_warning = None;
+        }
+        if dismiss_config_warning {
+            self.config_warning = None;
+        }
+        if dismiss_theme_warning {
+            self.theme_warning = None;
+        }
     }
     
     fn render_todo_list(&mut self, ui: &mut egui::Ui) {
@@ -941,7 +2206,7 @@ impl TodoApp {
         // Show new todo input at top if adding
         if is_adding_new {
             ui.horizontal(|ui| {
-                let bg_color = self.theme.accent.gamma_multiply(0.3);
+                let bg_color = self.theme.selection();
                 
                 let frame = egui::Frame::none()
                     .fill(bg_color)
@@ -974,6 +2239,10 @@ impl TodoApp {
                         Filter::All => "No todos yet. Press 'a' to add one!",
                         Filter::Active => "No active todos.",
                         Filter::Done => "No completed todos.",
+                        Filter::Today => "Nothing due today.",
+                        Filter::Overdue => "Nothing overdue.",
+                        Filter::Upcoming => "Nothing due in the next 7 days.",
+                        Filter::Archived => "Nothing archived yet.",
                     }
                 } else {
                     "No matching todos found."
@@ -984,15 +2253,15 @@ impl TodoApp {
             });
         } else if !filtered.is_empty() {
             // Collect data first to avoid borrow issues
+            let (range_start, range_end) = self.selection_range();
             let mut todo_data = Vec::new();
             for (i, (real_idx, todo)) in filtered.iter().enumerate() {
                 todo_data.push((
                     i,
                     *real_idx,
-                    todo.text.clone(),
-                    todo.project.clone(),
-                    todo.done,
+                    (*todo).clone(),
                     i == self.selected,
+                    self.visual_anchor.is_some() && i >= range_start && i <= range_end,
                     self.editing == Some(*real_idx)
                 ));
             }
@@ -1001,7 +2270,8 @@ impl TodoApp {
                 .auto_shrink([false; 2])
                 .max_height(ui.available_height() - 100.0) // Leave space for help text
                 .show(ui, |ui| {
-                    for (_i, _real_idx, text, project, done, is_selected, is_editing) in todo_data {
+                    for (_i, _real_idx, todo, is_selected, in_visual, is_editing) in todo_data {
+                        let done = todo.done;
                         
                         // If this item is selected, scroll to it
                         if is_selected {
@@ -1012,7 +2282,9 @@ impl TodoApp {
                         }
                         ui.horizontal(|ui| {
                             let bg_color = if is_selected {
-                                self.theme.accent.gamma_multiply(0.3)
+                                self.theme.selection()
+                            } else if in_visual {
+                                self.theme.visual_range()
                             } else {
                                 egui::Color32::TRANSPARENT
                             };
@@ -1037,32 +2309,74 @@ impl TodoApp {
                                 } else {
                                     ui.horizontal(|ui| {
                                         let checkbox_text = if done { "[x]" } else { "[ ]" };
-                                        let text_color = if done {
-                                            self.theme.done_color
-                                        } else {
-                                            self.theme.foreground
+                                        let text_color = match self.theme.selection_text {
+                                            _ if done => self.theme.done_color,
+                                            Some(color) if is_selected => color,
+                                            _ => self.theme.foreground,
                                         };
                                         
                                         ui.label(egui::RichText::new(checkbox_text)
                                             .color(if done { self.theme.accent } else { self.theme.border })
                                             .monospace());
                                         
+                                        if let Some(priority) = todo.priority {
+                                            let priority_color = match priority {
+                                                _ if done => None,
+                                                'A' => self.theme.normal.red,
+                                                'B' => self.theme.normal.yellow,
+                                                'C' => self.theme.normal.green,
+                                                _ => None,
+                                            };
+                                            ui.label(egui::RichText::new(format!("({})", priority))
+                                                .color(priority_color.unwrap_or(self.theme.done_color))
+                                                .monospace()
+                                                .strong());
+                                        }
+                                        
                                         // Show project name with project-specific color if present
-                                        if let Some(ref proj) = project {
-                                            let project_color = self.get_project_color(proj);
-                                            ui.label(egui::RichText::new(format!("{}: ", proj))
+                                        if let Some(ref proj) = todo.project {
+                                            let mut project_color = self.get_project_color(proj);
+                                            if done {
+                                                project_color = self.theme.dimmed(project_color);
+                                            }
+                                            let glyph = self.get_project_glyph(proj).map(|glyph| format!("{} ", glyph)).unwrap_or_default();
+                                            ui.label(egui::RichText::new(format!("{}{}: ", glyph, proj))
                                                 .color(project_color)
                                                 .strong());
                                         }
                                         
                                         let display_text = if done {
-                                            format!("~~{}~~", text)
+                                            format!("~~{}~~", todo.text)
                                         } else {
-                                            text
+                                            todo.text.clone()
                                         };
                                         
                                         ui.label(egui::RichText::new(display_text)
                                             .color(text_color));
+                                        
+                                        // Due date is shown on its own, colored by urgency
+                                        let mut todo = todo;
+                                        if let Some(due) = todo.due() {
+                                            let today = today();
+                                            let due_color = match (due - today).num_days() {
+                                                _ if done => self.theme.done_color,
+                                                ..=-1 => self.theme.normal.red.unwrap_or(self.theme.accent),
+                                                0 => self.theme.normal.yellow.unwrap_or(self.theme.accent),
+                                                _ => self.theme.done_color,
+                                            };
+                                            ui.label(egui::RichText::new(due_label(due, today))
+                                                .color(due_color)
+                                                .small());
+                                            todo.tags.retain(|(key, _)| key != "due");
+                                        }
+                                        
+                                        // @contexts and key:value tags, toned down
+                                        let metadata = todo.metadata_text();
+                                        if !metadata.is_empty() {
+                                            ui.label(egui::RichText::new(metadata.trim_start())
+                                                .color(self.theme.done_color)
+                                                .small());
+                                        }
                                     });
                                 }
                             });
@@ -1074,48 +2388,67 @@ impl TodoApp {
 }
 
 impl eframe::App for TodoApp {
-    fn update(&mut self, ctx: &egui::Context, frame: &mut eframe::Frame) {
+    fn update(&mut self, ctx: &egui::Context, _frame: &mut eframe::Frame) {
         // Enforce minimum window size at runtime
         ctx.input(|i| {
             if let Some(rect) = i.viewport().inner_rect {
                 let current_size = rect.size();
-                if current_size.x < 480.0 || current_size.y < 300.0 {
+                let window = self.config.window;
+                if current_size.x < window.min_width || current_size.y < window.min_height {
                     ctx.send_viewport_cmd(egui::ViewportCommand::InnerSize(egui::Vec2::new(
-                        current_size.x.max(480.0),
-                        current_size.y.max(300.0),
+                        current_size.x.max(window.min_width),
+                        current_size.y.max(window.min_height),
                     )));
                 }
             }
         });
         
-        // Hot-reload theme and todos less frequently to avoid blocking
-        if self.last_theme_check.elapsed() > Duration::from_millis(500) {
-            let old_bg = self.theme.background;
-            self.load_theme();
-            let new_bg = self.theme.background;
-            
-            // Also reload todos to pick up CLI changes
-            self.load_todos();
-            
-            // Force repaint if theme actually changed
-            if old_bg != new_bg {
-                ctx.request_repaint();
+        // Hot-reload config, theme and todos (e.g. after a CLI change)
+        if let Some(ref watcher) = self.watcher {
+            let config_changed = watcher.take_change(WatchKind::Config);
+            let theme_changed = watcher.take_change(WatchKind::Theme);
+            let todos_changed = watcher.take_change(WatchKind::Todos);
+            if config_changed {
+                self.reload_config();
+            }
+            if theme_changed {
+                // The theme's font may have been installed since it was missed
+                if self.font_resolver.retry_missing() {
+                    self.applied_font_family = None;
+                }
+                self.load_theme();
+            }
+            if todos_changed {
+                self.load_todos();
+            }
+        } else {
+            // The config isn't polled, and polling the theme keeps its
+            // warning: rereading them every time would bring back warnings
+            // the user dismissed
+            if self.last_theme_check.elapsed() > Duration::from_millis(500) {
+                let warning = self.theme_warning.take();
+                self.load_theme();
+                self.theme_warning = warning;
+                self.load_todos();
+                self.last_theme_check = Instant::now();
             }
             
-            self.last_theme_check = Instant::now();
+            // No file notifications available, so keep polling
+            ctx.request_repaint_after(Duration::from_millis(500));
         }
         
-        // Less frequent repaints to avoid blocking
-        ctx.request_repaint_after(Duration::from_millis(500));
-        
         self.handle_keyboard(ctx);
+        if let Some(text) = self.clipboard.take() {
+            ctx.output_mut(|o| o.copied_text = text);
+        }
         
         // Render project palette if open
         self.render_project_palette(ctx);
+        self.render_list_palette(ctx);
         
         // Semi-transparent background
         let mut bg_color = self.theme.background;
-        bg_color[3] = (255.0 * 0.85) as u8; // 85% opacity
+        bg_color[3] = (255.0 * self.config.opacity) as u8;
         
         let mut style = (*ctx.style()).clone();
         style.visuals.window_fill = bg_color;
@@ -1123,28 +2456,17 @@ impl eframe::App for TodoApp {
         style.visuals.extreme_bg_color = bg_color;
         style.visuals.faint_bg_color = self.theme.border;
         style.visuals.override_text_color = Some(self.theme.foreground);
-        style.visuals.selection.bg_fill = self.theme.accent;
+        style.visuals.selection.bg_fill = self.theme.selection_background.unwrap_or(self.theme.accent);
+        if let Some(text) = self.theme.selection_text {
+            style.visuals.selection.stroke.color = text;
+        }
+        if let Some(cursor) = self.theme.cursor {
+            style.visuals.text_cursor.stroke.color = cursor;
+        }
         
-        // Apply font configuration from theme
-        if let Some(ref font_family) = self.theme.font_family {
-            let mut fonts = egui::FontDefinitions::default();
-            
-            // Try to load the user's font family
-            if let Ok(font_data) = std::fs::read(format!("/usr/share/fonts/TTF/{}.ttf", font_family))
-                .or_else(|_| std::fs::read(format!("/usr/share/fonts/truetype/{}/{}.ttf", font_family.to_lowercase(), font_family)))
-                .or_else(|_| std::fs::read(format!("/System/Library/Fonts/{}.ttf", font_family)))
-                .or_else(|_| std::fs::read(format!("/System/Library/Fonts/{}.otf", font_family)))
-            {
-                fonts.font_data.insert(
-                    font_family.clone(),
-                    egui::FontData::from_owned(font_data),
-                );
-                fonts.families.entry(egui::FontFamily::Proportional).or_default()
-                    .insert(0, font_family.clone());
-                fonts.families.entry(egui::FontFamily::Monospace).or_default()
-                    .insert(0, font_family.clone());
-                ctx.set_fonts(fonts);
-            }
+        // Apply font configuration from theme (rebuilding the font atlas is expensive, so only on change)
+        if self.applied_font_family != self.theme.font_family {
+            self.apply_font_family(ctx);
         }
         
         // Apply font size (use effective font size that considers user override)
@@ -1174,7 +2496,7 @@ impl eframe::App for TodoApp {
                     ui.horizontal(|ui| {
                         // ASCII art on the left
                         ui.with_layout(egui::Layout::left_to_right(egui::Align::BOTTOM), |ui| {
-                            let ascii_art = vec![
+                            let ascii_art = [
                                 "  ▄██████▄    ▄▄▄▄███▄▄▄▄      ▄████████ ████████▄   ▄██████▄ ",
                                 " ███    ███ ▄██▀▀▀███▀▀▀██▄   ███    ███ ███   ▀███ ███    ███",
                                 " ███    ███ ███   ███   ███   ███    ███ ███    ███ ███    ███",
@@ -1212,11 +2534,39 @@ impl eframe::App for TodoApp {
                             ui.label(egui::RichText::new(format!("Filter: {}", self.filter.name()))
                                 .color(self.theme.done_color)
                                 .size(12.0));
+                            
+                            // Only worth the space once there's more than the default list
+                            if let Some(list) = lists::name_of(self.storage.path()).filter(|list| list != lists::DEFAULT_LIST) {
+                                ui.label(egui::RichText::new(" | ")
+                                    .color(self.theme.border)
+                                    .size(12.0));
+                                ui.label(egui::RichText::new(format!("List: {}", list))
+                                    .color(self.theme.done_color)
+                                    .size(12.0));
+                            }
+                            
+                            if !self.sort_by_priority {
+                                ui.label(egui::RichText::new(" | ")
+                                    .color(self.theme.border)
+                                    .size(12.0));
+                                ui.label(egui::RichText::new("File order")
+                                    .color(self.theme.done_color)
+                                    .size(12.0));
+                            }
+                            
+                            if self.dirty {
+                                ui.label(egui::RichText::new("● Unsaved  ")
+                                    .color(self.theme.normal.yellow.unwrap_or(self.theme.accent))
+                                    .size(12.0))
+                                    .on_hover_text("Changes haven't been written to disk yet");
+                            }
                         });
                     });
                     
                     ui.separator();
                     
+                    self.render_banner(ui);
+                    
                     // Conditional Search bar
                     if self.show_search {
                         ui.horizontal(|ui| {
@@ -1241,9 +2591,13 @@ impl eframe::App for TodoApp {
                     ui.add_space(5.0);
                     ui.horizontal(|ui| {
                         let help_text = if self.editing.is_some() {
-                            "Enter: Save | Esc: Cancel"
+                            "Enter: Save | Esc: Cancel".to_string()
                         } else {
-                            "j/k: Move | Enter: Edit | a: Add | x: Toggle | dd: Delete | f: Filter | p: Project | Shift+S: Search | Shift+P: Projects | c: Clear Filters | Ctrl+/- : Font Size"
+                            self.keymap.help(match (self.filter, self.visual_anchor) {
+                                (Filter::Archived, _) => HelpMode::Archive,
+                                (_, Some(_)) => HelpMode::Visual,
+                                (_, None) => HelpMode::Normal,
+                            })
                         };
                         
                         let help_size = self.get_effective_font_size() * 0.9;
@@ -1258,118 +2612,37 @@ impl eframe::App for TodoApp {
                     });
                     ui.add_space(5.0);
                     
-                    if self.delete_mode {
+                    if self.visual_anchor.is_some() {
+                        let (start, end) = self.selection_range();
                         ui.horizontal(|ui| {
-                            let delete_size = self.get_effective_font_size() * 0.9;
-                            ui.label(egui::RichText::new("Press 'd' again to delete selected item")
-                                .color(egui::Color32::from_rgb(255, 100, 100))
-                                .size(delete_size));
+                            ui.label(egui::RichText::new(format!("-- VISUAL -- {} selected", end - start + 1))
+                                .color(self.theme.accent)
+                                .size(self.get_effective_font_size() * 0.9)
+                                .strong());
                         });
                     }
-                });
-            });
-    }
-}
-
-fn handle_cli_command(args: Vec<String>) -> Result<(), Box<dyn std::error::Error>> {
-    if args.len() < 2 {
-        return Ok(()); // No CLI args, run GUI
-    }
-    
-    match args[1].as_str() {
-        "add" => {
-            if args.len() < 3 {
-                eprintln!("Usage: omado add \"<task>\"");
-                std::process::exit(1);
-            }
-            
-            let task_text = args[2].clone();
-            let (text, project) = TodoApp::parse_todo_text(&task_text);
-            
-            let todo = Todo {
-                text,
-                project,
-                done: false,
-            };
-            
-            // Load existing todos
-            let storage_path = TodoApp::get_storage_path();
-            let mut todos = Vec::new();
-            
-            if let Ok(content) = fs::read_to_string(&storage_path) {
-                for line in content.lines() {
-                    let line = line.trim();
-                    if line.starts_with("[ ] ") {
-                        let (text, project) = TodoApp::parse_todo_text(&line[4..]);
-                        todos.push(Todo {
-                            text,
-                            done: false,
-                            project,
-                        });
-                    } else if line.starts_with("[x] ") {
-                        let (text, project) = TodoApp::parse_todo_text(&line[4..]);
-                        todos.push(Todo {
-                            text,
-                            done: true,
-                            project,
+                    
+                    // Unfinished command such as `3d`
+                    if !self.key_parser.pending().is_empty() {
+                        ui.horizontal(|ui| {
+                            let pending_size = self.get_effective_font_size() * 0.9;
+                            ui.label(egui::RichText::new(format!("Pending: {}", self.key_parser.pending()))
+                                .color(self.theme.accent)
+                                .size(pending_size));
                         });
                     }
-                }
-            }
-            
-            // Add new todo
-            todos.push(todo.clone());
-            
-            // Save todos
-            let mut content = String::new();
-            for todo in &todos {
-                let prefix = if todo.done { "[x]" } else { "[ ]" };
-                let display_text = if let Some(ref project) = todo.project {
-                    format!("{}: {}", project, todo.text)
-                } else {
-                    todo.text.clone()
-                };
-                content.push_str(&format!("{} {}\n", prefix, display_text));
-            }
-            fs::write(&storage_path, content)?;
-            
-            // Confirmation message
-            if let Some(ref project) = todo.project {
-                println!("✓ Added task to project '{}': {}", project, todo.text);
-            } else {
-                println!("✓ Added task: {}", todo.text);
-            }
-            
-            std::process::exit(0);
-        }
-        "help" | "--help" | "-h" => {
-            println!("omado - Simple todo management");
-            println!();
-            println!("USAGE:");
-            println!("    omado                    Launch GUI");
-            println!("    omado add \"<task>\"       Add a new task");
-            println!("    omado help               Show this help");
-            println!();
-            println!("EXAMPLES:");
-            println!("    omado add \"Buy groceries\"");
-            println!("    omado add \"work: Fix parser bug\"");
-            println!("    omado add \"personal: Call mom\"");
-            
-            std::process::exit(0);
-        }
-        _ => {
-            eprintln!("Unknown command: {}", args[1]);
-            eprintln!("Run 'omado help' for usage information.");
-            std::process::exit(1);
-        }
+                });
+            });
     }
 }
 
 fn main() -> Result<(), eframe::Error> {
-    let args: Vec<String> = std::env::args().collect();
+    let mut args: Vec<String> = std::env::args().collect();
+    let overrides = cli::take_overrides(&mut args);
+    let (config, config_warning) = Config::load_with(&overrides);
     
     // Handle CLI commands
-    if let Err(e) = handle_cli_command(args) {
+    if let Err(e) = cli::handle_cli_command(args, &config, config_warning.as_deref()) {
         eprintln!("Error: {}", e);
         std::process::exit(1);
     }
@@ -1409,8 +2682,8 @@ fn main() -> Result<(), eframe::Error> {
     // Launch GUI
     let options = eframe::NativeOptions {
         viewport: egui::ViewportBuilder::default()
-            .with_inner_size([520.0, 640.0])
-            .with_min_inner_size([480.0, 300.0]) // Minimum width to fit ASCII art + padding
+            .with_inner_size([config.window.width, config.window.height])
+            .with_min_inner_size([config.window.min_width, config.window.min_height])
             .with_decorations(false)
             .with_resizable(true)
             .with_title("omado")
@@ -1421,6 +2694,141 @@ fn main() -> Result<(), eframe::Error> {
     eframe::run_native(
         "omado",
         options,
-        Box::new(|_cc| Ok(Box::new(TodoApp::new()))),
+        Box::new(|cc| Ok(Box::new(TodoApp::new(&cc.egui_ctx, config, config_warning, overrides)))),
     )
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn date(y: i32, m: u32, d: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(y, m, d).unwrap()
+    }
+
+    #[test]
+    fn resolves_due_dates() {
+        // A Friday
+        let today = date(2026, 10, 16);
+        let cases = [
+            ("2026-12-01", Some(date(2026, 12, 1))),
+            ("today", Some(today)),
+            ("Tomorrow", Some(date(2026, 10, 17))),
+            ("tom", Some(date(2026, 10, 17))),
+            ("sat", Some(date(2026, 10, 17))),
+            ("mon", Some(date(2026, 10, 19))),
+            ("Thursday", Some(date(2026, 10, 22))),
+            // The same weekday means next week's
+            ("fri", Some(date(2026, 10, 23))),
+            ("friday", Some(date(2026, 10, 23))),
+            ("3d", Some(date(2026, 10, 19))),
+            ("0d", Some(today)),
+            ("2w", Some(date(2026, 10, 30))),
+            ("someday", None),
+            ("3x", None),
+            ("ü", None),
+            ("3ü", None),
+            ("2026-13-01", None),
+            ("", None),
+        ];
+        for (value, expected) in cases {
+            assert_eq!(resolve_date(value, today), expected, "due:{}", value);
+        }
+        assert_eq!(resolve_date("tomorrow", date(2026, 12, 31)), Some(date(2027, 1, 1)));
+    }
+
+    #[test]
+    fn labels_due_dates() {
+        let today = date(2026, 10, 16);
+        let cases = [
+            (date(2026, 10, 13), "overdue 3d"),
+            (date(2026, 10, 15), "overdue 1d"),
+            (today, "due today"),
+            (date(2026, 10, 17), "due tomorrow"),
+            (date(2026, 10, 18), "due Sun"),
+            (date(2026, 10, 22), "due Thu"),
+            (date(2026, 10, 23), "due Oct 23"),
+            (date(2027, 1, 5), "due 2027-01-05"),
+        ];
+        for (due, expected) in cases {
+            assert_eq!(due_label(due, today), expected);
+        }
+    }
+
+    #[test]
+    fn due_filters_split_at_today_and_a_week_out() {
+        let today = date(2026, 10, 16);
+        let shown_by = |input: &str| -> Vec<&str> {
+            let todo = Todo::from_input(input);
+            [Filter::Overdue, Filter::Today, Filter::Upcoming]
+                .into_iter()
+                .filter(|filter| filter.matches(&todo, today))
+                .map(Filter::name)
+                .collect()
+        };
+        assert_eq!(shown_by("a due:2026-10-15"), ["Overdue"]);
+        assert_eq!(shown_by("a due:2026-10-16"), ["Today"]);
+        assert_eq!(shown_by("a due:2026-10-17"), ["Upcoming"]);
+        assert_eq!(shown_by("a due:2026-10-23"), ["Upcoming"]);
+        assert!(shown_by("a due:2026-10-24").is_empty());
+        assert!(shown_by("a").is_empty());
+
+        let mut done = Todo::from_input("a due:2026-10-15");
+        done.done = true;
+        assert!(!Filter::Overdue.matches(&done, today));
+        assert!(Filter::Done.matches(&done, today) && !Filter::Active.matches(&done, today));
+
+        assert!(Filter::from_due_name("Today") == Some(Filter::Today));
+        assert!(Filter::from_due_name("OVERDUE") == Some(Filter::Overdue));
+        assert!(Filter::from_due_name("done").is_none());
+    }
+
+    #[test]
+    fn next_due_dates() {
+        let today = date(2026, 10, 16);
+        let cases = [
+            // Plain intervals count from the day the task is completed...
+            ("daily", Some(date(2026, 10, 10)), today, Some(date(2026, 10, 17))),
+            ("weekly", None, today, Some(date(2026, 10, 23))),
+            ("3d", Some(date(2026, 10, 20)), today, Some(date(2026, 10, 19))),
+            ("yearly", None, date(2028, 2, 29), Some(date(2029, 2, 28))),
+            // ...strict ones from the old due date, or today without one
+            ("+daily", Some(date(2026, 10, 10)), today, Some(date(2026, 10, 11))),
+            ("+2w", Some(date(2026, 10, 2)), today, Some(date(2026, 10, 16))),
+            ("+1w", None, today, Some(date(2026, 10, 23))),
+            // Months that are too short clamp to their last day
+            ("monthly", None, date(2026, 1, 31), Some(date(2026, 2, 28))),
+            ("+1m", Some(date(2026, 1, 31)), date(2026, 2, 5), Some(date(2026, 2, 28))),
+            ("+1m", Some(date(2028, 1, 31)), date(2028, 2, 5), Some(date(2028, 2, 29))),
+            ("monthly-on-31st", Some(date(2026, 1, 31)), date(2026, 1, 31), Some(date(2026, 2, 28))),
+            ("monthly-on-31st", Some(date(2026, 2, 28)), date(2026, 2, 28), Some(date(2026, 3, 31))),
+            ("monthly-on-1st", None, today, Some(date(2026, 11, 1))),
+            ("Monthly-On-15th", Some(date(2026, 10, 1)), today, Some(date(2026, 11, 15))),
+            ("monthly-on-16th", Some(date(2026, 10, 20)), today, Some(date(2026, 11, 16))),
+            // Rules that don't parse don't recur
+            ("0d", None, today, None),
+            ("fortnightly", None, today, None),
+            ("monthly-on-32nd", None, today, None),
+            ("é", None, today, None),
+            ("+2é", None, today, None),
+            ("", None, today, None),
+        ];
+        for (rule, due, today, expected) in cases {
+            assert_eq!(next_due_date(rule, due, today), expected, "rec:{} due {:?} on {}", rule, due, today);
+        }
+    }
+
+    #[test]
+    fn project_moves_stay_out_of_their_own_subprojects() {
+        let todos: Vec<Todo> = ["work: a", "work:backend: b", "home: c"].into_iter().map(Todo::from_input).collect();
+        assert!(check_project_move(&todos, "work", "home", true).is_ok());
+        assert!(check_project_move(&todos, "work", "job", false).is_ok());
+        assert!(check_project_move(&todos, "work", "work", true).is_err());
+        assert!(check_project_move(&todos, "work", "work:backend", true).is_err());
+        assert!(check_project_move(&todos, "work:backend", "work", true).is_ok());
+        // Renaming never merges
+        assert!(check_project_move(&todos, "work", "home", false).is_err());
+        assert!(check_project_move(&todos, "work", "my work", false).is_err());
+        assert!(check_project_move(&todos, "work", "job", true).is_err());
+    }
+}
@@ -0,0 +1,191 @@
+use std::ffi::OsString;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Write};
+use std::os::unix::io::AsRawFd;
+use std::path::{Path, PathBuf};
+
+use crate::document::{Format, TodoDocument};
+
+/// The todo file on disk, shared between the GUI and any number of CLI
+/// invocations.
+///
+/// Writers serialize through an advisory `flock` on a sidecar lock file and
+/// replace the todo file with an atomic rename, so readers never observe a
+/// partially written file and concurrent read-modify-write cycles don't
+/// lose each other's changes.
+///
+/// The file's format is remembered in a sidecar once omado has written it, so
+/// it is only guessed from the content for files omado hasn't saved yet.
+pub(crate) struct Storage {
+    path: PathBuf,
+}
+
+/// Exclusive lock on the todo file, released when dropped.
+pub(crate) struct StorageLock {
+    _file: File,
+}
+
+impl Storage {
+    pub fn new(path: PathBuf) -> Self {
+        Self { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Reads the todo file. A missing file is an empty list.
+    pub fn load(&self) -> io::Result<TodoDocument> {
+        let content = match fs::read_to_string(&self.path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => String::new(),
+            Err(e) => return Err(e),
+        };
+        Ok(match self.saved_format() {
+            Some(format) => TodoDocument::parse_as(&content, format),
+            None => TodoDocument::parse(&content),
+        })
+    }
+
+    /// A hidden file next to the todo file holding something about it, e.g.
+    /// `.todo.txt.format`.
+    pub fn sidecar(&self, suffix: &str) -> PathBuf {
+        sibling_of(&self.target(), suffix)
+    }
+
+    /// The format the file was last saved in, if omado has saved it.
+    fn saved_format(&self) -> Option<Format> {
+        let name = fs::read_to_string(self.sidecar(".format")).ok()?;
+        Format::from_name(name.trim())
+    }
+
+    /// Blocks until no other omado process is writing the todo file.
+    pub fn lock(&self) -> io::Result<StorageLock> {
+        let file = OpenOptions::new()
+            .create(true)
+            .truncate(false)
+            .write(true)
+            .open(self.sidecar(".lock"))?;
+
+        loop {
+            if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX) } == 0 {
+                return Ok(StorageLock { _file: file });
+            }
+            let err = io::Error::last_os_error();
+            if err.kind() != io::ErrorKind::Interrupted {
+                return Err(err);
+            }
+        }
+    }
+
+    /// Atomically replaces the todo file with `doc`. Requires holding the lock
+    /// so that the temp file name can't collide with another writer.
+    ///
+    /// Returns a warning if the tasks were saved but their format couldn't be
+    /// recorded, in which case it is guessed again on the next load.
+    pub fn save(&self, _lock: &StorageLock, doc: &TodoDocument) -> io::Result<Option<String>> {
+        let target = self.target();
+        let tmp = sibling_of(&target, ".tmp");
+
+        let result = (|| {
+            let mut file = File::create(&tmp)?;
+            if let Ok(meta) = fs::metadata(&target) {
+                file.set_permissions(meta.permissions())?;
+            }
+            file.write_all(doc.contents().as_bytes())?;
+            file.sync_all()?;
+            fs::rename(&tmp, &target)?;
+            if let Some(dir) = target.parent().filter(|d| !d.as_os_str().is_empty()) {
+                File::open(dir)?.sync_all()?;
+            }
+            Ok(())
+        })();
+
+        if let Err(e) = result {
+            let _ = fs::remove_file(&tmp);
+            return Err(e);
+        }
+        if self.saved_format() != Some(doc.format()) {
+            if let Err(e) = fs::write(sibling_of(&target, ".format"), format!("{}\n", doc.format().name())) {
+                return Ok(Some(format!("Couldn't record that {} is in {} format: {}", target.display(), doc.format().name(), e)));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Locked read-modify-write. The file is only rewritten if `change`
+    /// succeeds. Warnings from saving go to stderr.
+    pub fn update<R, E>(&self, change: impl FnOnce(&mut TodoDocument) -> Result<R, E>) -> Result<R, E>
+    where
+        E: From<io::Error>,
+    {
+        let lock = self.lock()?;
+        let mut doc = self.load()?;
+        let result = change(&mut doc)?;
+        if let Some(warning) = self.save(&lock, &doc)? {
+            eprintln!("Warning: {}", warning);
+        }
+        Ok(result)
+    }
+
+    /// Resolves symlinks (e.g. a todo.txt kept in a dotfiles repo) so that
+    /// saving replaces the real file instead of the link.
+    fn target(&self) -> PathBuf {
+        fs::canonicalize(&self.path).unwrap_or_else(|_| self.path.clone())
+    }
+}
+
+/// `dir/todo.txt` -> `dir/.todo.txt<suffix>`
+fn sibling_of(path: &Path, suffix: &str) -> PathBuf {
+    let mut name = OsString::from(".");
+    name.push(path.file_name().unwrap_or_default());
+    name.push(suffix);
+    path.with_file_name(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Todo;
+
+    #[test]
+    fn emptied_todotxt_file_keeps_its_format() {
+        let dir = std::env::temp_dir().join(format!("omado-storage-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let storage = Storage::new(dir.join("todo.txt"));
+        fs::write(storage.path(), "x 2026-10-16 Pay rent\n").unwrap();
+
+        // Archiving every task leaves nothing to guess the format from
+        storage
+            .update(|todos| -> io::Result<()> {
+                todos.remove(0);
+                Ok(())
+            })
+            .unwrap();
+        storage
+            .update(|todos| -> io::Result<()> {
+                assert_eq!(todos.format(), Format::TodoTxt);
+                todos.push(Todo::from_input("Buy milk +home"));
+                Ok(())
+            })
+            .unwrap();
+        let content = fs::read_to_string(storage.path()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(content.ends_with(" Buy milk +home\n"), "{}", content);
+    }
+
+    #[test]
+    fn unrecorded_format_is_a_warning() {
+        let dir = std::env::temp_dir().join(format!("omado-storage-format-{}", std::process::id()));
+        fs::create_dir_all(dir.join(".todo.txt.format")).unwrap();
+        let storage = Storage::new(dir.join("todo.txt"));
+
+        let mut doc = TodoDocument::default();
+        doc.push(Todo::from_input("Buy milk"));
+        let result = storage.lock().and_then(|lock| storage.save(&lock, &doc));
+        let content = fs::read_to_string(storage.path()).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        assert!(result.unwrap().is_some());
+        assert_eq!(content, "[ ] Buy milk\n");
+    }
+}
@@ -0,0 +1,865 @@
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use anyhow::Result;
+use eframe::egui;
+use serde::{Deserialize, Serialize};
+
+use crate::config;
+
+/// Where the colors and font come from: a terminal's config, or the theme
+/// Omarchy currently applies to all of them.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum ThemeSource {
+    Alacritty,
+    Kitty,
+    Ghostty,
+    Foot,
+    Omarchy,
+}
+
+impl ThemeSource {
+    /// In the order `auto` tries them.
+    pub const ALL: [ThemeSource; 5] =
+        [ThemeSource::Alacritty, ThemeSource::Kitty, ThemeSource::Ghostty, ThemeSource::Foot, ThemeSource::Omarchy];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ThemeSource::Alacritty => "alacritty",
+            ThemeSource::Kitty => "kitty",
+            ThemeSource::Ghostty => "ghostty",
+            ThemeSource::Foot => "foot",
+            ThemeSource::Omarchy => "omarchy",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|source| source.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The config file read for this source, or the theme directory for Omarchy.
+    pub fn config_path(self) -> Option<PathBuf> {
+        let home = config::config_home()?;
+        Some(match self {
+            ThemeSource::Alacritty => home.join("alacritty").join("alacritty.toml"),
+            ThemeSource::Kitty => home.join("kitty").join("kitty.conf"),
+            ThemeSource::Ghostty => {
+                // Newer Ghostty versions prefer the name with an extension
+                let dir = home.join("ghostty");
+                let named = dir.join("config.ghostty");
+                if named.exists() { named } else { dir.join("config") }
+            }
+            ThemeSource::Foot => home.join("foot").join("foot.ini"),
+            ThemeSource::Omarchy => home.join("omarchy").join("current").join("theme"),
+        })
+    }
+
+    /// The source a `theme` setting asks for. `auto` prefers the terminal in
+    /// `$TERMINAL`, then the first source that's configured, if any.
+    pub fn choose(setting: &str) -> Option<Self> {
+        if !setting.eq_ignore_ascii_case("auto") {
+            return Self::from_name(setting);
+        }
+        let exists = |source: &Self| source.config_path().is_some_and(|path| path.exists());
+        let terminal = std::env::var("TERMINAL").ok().and_then(|terminal| {
+            let name = Path::new(&terminal).file_name()?.to_string_lossy().into_owned();
+            Self::from_name(&name)
+        });
+        terminal.filter(exists).or_else(|| Self::ALL.into_iter().find(exists))
+    }
+}
+
+/// A color a theme can set, whatever the terminal calls it.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+enum Slot {
+    Background,
+    Foreground,
+    DimForeground,
+    Cursor,
+    SelectionText,
+    SelectionBackground,
+    Normal(usize),
+    Bright(usize),
+    Dim(usize),
+}
+
+const ANSI_NAMES: [&str; 8] = ["black", "red", "green", "yellow", "blue", "magenta", "cyan", "white"];
+
+impl Slot {
+    /// The same whatever the terminal calls it, e.g. `bright.red`.
+    fn name(self) -> String {
+        match self {
+            Slot::Background => "background".to_string(),
+            Slot::Foreground => "foreground".to_string(),
+            Slot::DimForeground => "dim_foreground".to_string(),
+            Slot::Cursor => "cursor".to_string(),
+            Slot::SelectionText => "selection_text".to_string(),
+            Slot::SelectionBackground => "selection_background".to_string(),
+            Slot::Normal(index) => format!("normal.{}", ANSI_NAMES[index]),
+            Slot::Bright(index) => format!("bright.{}", ANSI_NAMES[index]),
+            Slot::Dim(index) => format!("dim.{}", ANSI_NAMES[index]),
+        }
+    }
+
+    /// A color named in omado's config: `foreground`, or one of the ANSI
+    /// colors as `red`, `normal.red`, `bright.red` or `dim.red`.
+    fn named(name: &str) -> Option<Slot> {
+        if name == "foreground" {
+            return Some(Slot::Foreground);
+        }
+        let (set, color) = name.split_once('.').unwrap_or(("normal", name));
+        let index = ANSI_NAMES.iter().position(|ansi| *ansi == color)?;
+        match set {
+            "normal" => Some(Slot::Normal(index)),
+            "bright" => Some(Slot::Bright(index)),
+            "dim" => Some(Slot::Dim(index)),
+            _ => None,
+        }
+    }
+
+    /// `color0` to `color15` in Kitty, Ghostty and the like.
+    fn ansi(index: usize) -> Option<Slot> {
+        match index {
+            0..=7 => Some(Slot::Normal(index)),
+            8..=15 => Some(Slot::Bright(index - 8)),
+            _ => None,
+        }
+    }
+}
+
+/// The 8 ANSI colors of one palette set, `None` where the theme leaves them out.
+#[derive(Clone, Copy, Default)]
+pub(crate) struct AnsiColors {
+    pub black: Option<egui::Color32>,
+    pub red: Option<egui::Color32>,
+    pub green: Option<egui::Color32>,
+    pub yellow: Option<egui::Color32>,
+    pub blue: Option<egui::Color32>,
+    pub magenta: Option<egui::Color32>,
+    pub cyan: Option<egui::Color32>,
+    pub white: Option<egui::Color32>,
+}
+
+impl AnsiColors {
+    /// Takes the colors `other` sets, keeping the rest.
+    fn merge(&mut self, other: AnsiColors) {
+        for (index, color) in other.colors().into_iter().enumerate() {
+            if let Some(color) = color {
+                self.set(index, color);
+            }
+        }
+    }
+
+    fn set(&mut self, index: usize, color: egui::Color32) {
+        let slot = match index {
+            0 => &mut self.black,
+            1 => &mut self.red,
+            2 => &mut self.green,
+            3 => &mut self.yellow,
+            4 => &mut self.blue,
+            5 => &mut self.magenta,
+            6 => &mut self.cyan,
+            7 => &mut self.white,
+            _ => return,
+        };
+        *slot = Some(color);
+    }
+
+    /// In ANSI order, black to white.
+    pub fn colors(&self) -> [Option<egui::Color32>; 8] {
+        [self.black, self.red, self.green, self.yellow, self.blue, self.magenta, self.cyan, self.white]
+    }
+}
+
+/// What the theme files set, before falling back to defaults.
+#[derive(Default)]
+struct Palette {
+    background: Option<egui::Color32>,
+    foreground: Option<egui::Color32>,
+    dim_foreground: Option<egui::Color32>,
+    cursor: Option<egui::Color32>,
+    selection_text: Option<egui::Color32>,
+    selection_background: Option<egui::Color32>,
+    normal: AnsiColors,
+    bright: AnsiColors,
+    dim: AnsiColors,
+    font_family: Option<String>,
+    font_size: Option<f32>,
+}
+
+impl Palette {
+    fn set(&mut self, slot: Slot, color: egui::Color32) {
+        match slot {
+            Slot::Background => self.background = Some(color),
+            Slot::Foreground => self.foreground = Some(color),
+            Slot::DimForeground => self.dim_foreground = Some(color),
+            Slot::Cursor => self.cursor = Some(color),
+            Slot::SelectionText => self.selection_text = Some(color),
+            Slot::SelectionBackground => self.selection_background = Some(color),
+            Slot::Normal(index) => self.normal.set(index, color),
+            Slot::Bright(index) => self.bright.set(index, color),
+            Slot::Dim(index) => self.dim.set(index, color),
+        }
+    }
+}
+
+pub(crate) struct Theme {
+    pub background: egui::Color32,
+    pub foreground: egui::Color32,
+    pub accent: egui::Color32,
+    pub border: egui::Color32,
+    pub done_color: egui::Color32,
+    pub cursor: Option<egui::Color32>,
+    pub selection_text: Option<egui::Color32>,
+    pub selection_background: Option<egui::Color32>,
+    pub normal: AnsiColors,
+    pub bright: AnsiColors,
+    pub dim: AnsiColors,
+    pub font_family: Option<String>,
+    pub font_size: Option<f32>,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self {
+            background: egui::Color32::from_rgb(26, 27, 38),
+            foreground: egui::Color32::from_rgb(205, 214, 244),
+            accent: egui::Color32::from_rgb(116, 199, 236),
+            border: egui::Color32::from_rgb(88, 91, 112),
+            done_color: egui::Color32::from_rgb(166, 173, 200),
+            cursor: None,
+            selection_text: None,
+            selection_background: None,
+            // Default Catppuccin-like colors for projects
+            normal: AnsiColors {
+                black: None,
+                red: Some(egui::Color32::from_rgb(243, 139, 168)),
+                green: Some(egui::Color32::from_rgb(166, 227, 161)),
+                yellow: Some(egui::Color32::from_rgb(249, 226, 175)),
+                blue: Some(egui::Color32::from_rgb(137, 180, 250)),
+                magenta: Some(egui::Color32::from_rgb(203, 166, 247)),
+                cyan: Some(egui::Color32::from_rgb(148, 226, 213)),
+                white: Some(egui::Color32::from_rgb(205, 214, 244)),
+            },
+            bright: AnsiColors::default(),
+            dim: AnsiColors::default(),
+            font_family: None,
+            font_size: None,
+        }
+    }
+}
+
+impl Theme {
+    fn from_palette(palette: Palette) -> Self {
+        let mut theme = Theme::default();
+        let normal = palette.normal;
+        theme.background = palette.background.unwrap_or(theme.background);
+        theme.foreground = palette.foreground.unwrap_or(theme.foreground);
+        theme.accent = normal.blue.unwrap_or(theme.accent);
+        theme.border = normal.white.unwrap_or(theme.border);
+        // The theme's own color for dimmed text beats borrowing a normal one
+        theme.done_color = palette.dim_foreground.or(normal.cyan).or(normal.black).unwrap_or(theme.done_color);
+        theme.cursor = palette.cursor;
+        theme.selection_text = palette.selection_text;
+        theme.selection_background = palette.selection_background;
+        theme.normal.merge(normal);
+        theme.bright = palette.bright;
+        theme.dim = palette.dim;
+        theme.font_family = palette.font_family;
+        theme.font_size = palette.font_size;
+        theme
+    }
+
+    /// Background of the selected task, and of the selected entry in palettes.
+    pub fn selection(&self) -> egui::Color32 {
+        self.selection_background.unwrap_or_else(|| self.accent.gamma_multiply(0.3))
+    }
+
+    /// Background of the other tasks in the visual mode range.
+    pub fn visual_range(&self) -> egui::Color32 {
+        match self.selection_background {
+            Some(background) => background.gamma_multiply(0.5),
+            None => self.accent.gamma_multiply(0.15),
+        }
+    }
+
+    /// A color from omado's config, by palette name or hex value. Bright and
+    /// dim colors the theme doesn't set fall back to the normal ones.
+    pub fn named_color(&self, value: &str) -> Option<egui::Color32> {
+        match Slot::named(value) {
+            Some(Slot::Foreground) => Some(self.foreground),
+            Some(Slot::Normal(index)) => self.normal.colors()[index],
+            Some(Slot::Bright(index)) => self.bright.colors()[index].or(self.normal.colors()[index]),
+            Some(Slot::Dim(index)) => self.dim.colors()[index].or(self.normal.colors()[index]),
+            Some(_) => None,
+            None => parse_hex_color(value).ok(),
+        }
+    }
+
+    /// `color` as the theme dims text: its `dim` counterpart when it's one of
+    /// the normal or bright colors and the theme has one.
+    pub fn dimmed(&self, color: egui::Color32) -> egui::Color32 {
+        let (normal, bright, dim) = (self.normal.colors(), self.bright.colors(), self.dim.colors());
+        (0..8)
+            .find(|&i| normal[i] == Some(color) || bright[i] == Some(color))
+            .and_then(|i| dim[i])
+            .unwrap_or(color)
+    }
+}
+
+/// A value one of the theme files set, and where.
+#[derive(Serialize)]
+pub(crate) struct ThemeValue {
+    /// The setting, named the same whatever the terminal, e.g. `normal.blue`.
+    pub name: String,
+    pub value: String,
+    pub file: PathBuf,
+    /// What the file calls it, e.g. `color4`.
+    pub key: String,
+}
+
+/// A theme and what went into it.
+pub(crate) struct LoadedTheme {
+    pub theme: Theme,
+    pub source: Option<ThemeSource>,
+    /// Every file the theme was read from, in load order, including missing
+    /// ones that would be read once they're created.
+    pub files: Vec<PathBuf>,
+    /// Unreadable files and values, each starting with the file's path.
+    pub warnings: Vec<String>,
+    /// The values the files set, each with the file it last came from.
+    pub values: Vec<ThemeValue>,
+}
+
+/// Loads the theme picked by the `theme` setting.
+pub(crate) fn load(setting: &str) -> LoadedTheme {
+    let mut loader = Loader::default();
+    let source = ThemeSource::choose(setting);
+    if let Some(source) = source {
+        if let Some(path) = source.config_path() {
+            loader.load_source(source, &path);
+        }
+    }
+    LoadedTheme {
+        theme: Theme::from_palette(loader.palette),
+        source,
+        files: loader.files,
+        warnings: loader.warnings,
+        values: loader.values,
+    }
+}
+
+// Values that stand for a color the terminal picks, rather than a color
+const KEYWORDS: [&str; 7] =
+    ["CellForeground", "CellBackground", "cell-foreground", "cell-background", "none", "foreground", "background"];
+
+#[derive(Default)]
+struct Loader {
+    palette: Palette,
+    files: Vec<PathBuf>,
+    warnings: Vec<String>,
+    values: Vec<ThemeValue>,
+    // Files being read, innermost last, to break include cycles
+    reading: Vec<PathBuf>,
+}
+
+impl Loader {
+    fn load_source(&mut self, source: ThemeSource, path: &Path) {
+        match source {
+            ThemeSource::Alacritty => self.load_file(path, Self::parse_alacritty),
+            ThemeSource::Kitty => self.load_file(path, Self::parse_kitty),
+            ThemeSource::Ghostty => self.load_file(path, Self::parse_ghostty),
+            ThemeSource::Foot => self.load_file(path, Self::parse_foot),
+            ThemeSource::Omarchy => {
+                // Omarchy themes carry a config for each terminal it supports
+                let files = [
+                    ("alacritty.toml", ThemeSource::Alacritty),
+                    ("kitty.conf", ThemeSource::Kitty),
+                    ("ghostty.conf", ThemeSource::Ghostty),
+                    ("foot.ini", ThemeSource::Foot),
+                ];
+                match files.iter().find(|(name, _)| path.join(name).exists()) {
+                    Some((name, source)) => self.load_source(*source, &path.join(name)),
+                    // Watch it anyway so the theme updates once one is set
+                    None => self.files.push(path.join(files[0].0)),
+                }
+            }
+        }
+    }
+
+    /// Reads `path` with `parse`, unless it's already being read further up
+    /// an include chain.
+    fn load_file(&mut self, path: &Path, parse: fn(&mut Self, &Path, &str)) {
+        let key = path.canonicalize().unwrap_or_else(|_| path.to_path_buf());
+        if self.reading.contains(&key) {
+            let including = self.reading.last().cloned().unwrap_or_default();
+            self.warn(&including, format!("skipped including {}, which would include this file again", path.display()));
+            return;
+        }
+        self.files.push(path.to_path_buf());
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            // Optional, like in the terminals themselves
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return,
+            Err(e) => return self.warn(path, format!("couldn't read it: {}", e)),
+        };
+        self.reading.push(key);
+        parse(self, path, &content);
+        self.reading.pop();
+    }
+
+    fn warn(&mut self, path: &Path, message: String) {
+        self.warnings.push(format!("{}: {}", path.display(), message));
+    }
+
+    /// Sets `slot` from the value of `key`, if it's a color. Keywords such as
+    /// Alacritty's `CellForeground` or Kitty's `none` are skipped quietly.
+    fn set_color(&mut self, path: &Path, key: &str, slot: Option<Slot>, value: &str) {
+        let Some(slot) = slot else { return };
+        match parse_hex_color(value) {
+            Ok(color) => {
+                self.palette.set(slot, color);
+                self.record(slot.name(), hex(color), path, key);
+            }
+            Err(_) if KEYWORDS.iter().any(|keyword| keyword.eq_ignore_ascii_case(value)) => {}
+            Err(e) => self.warn(path, format!("{} {}", key, e)),
+        }
+    }
+
+    fn set_font_family(&mut self, path: &Path, key: &str, family: &str) {
+        self.palette.font_family = Some(family.to_string());
+        self.record("font_family".to_string(), family.to_string(), path, key);
+    }
+
+    fn set_font_size(&mut self, path: &Path, key: &str, value: &str) {
+        match value.parse::<f32>() {
+            Ok(size) if size > 0.0 => {
+                self.palette.font_size = Some(size);
+                self.record("font_size".to_string(), size.to_string(), path, key);
+            }
+            _ => self.warn(path, format!("{} \"{}\" isn't a font size", key, value)),
+        }
+    }
+
+    /// Remembers where `name` got its value, replacing what an earlier file set.
+    fn record(&mut self, name: String, value: String, path: &Path, key: &str) {
+        let value = ThemeValue { name, value, file: path.to_path_buf(), key: key.to_string() };
+        match self.values.iter_mut().find(|old| old.name == value.name) {
+            Some(old) => *old = value,
+            None => self.values.push(value),
+        }
+    }
+
+    /// `alacritty.toml`, with imports read before the file's own settings so
+    /// those win.
+    fn parse_alacritty(&mut self, path: &Path, content: &str) {
+        let config: AlacrittyConfig = match toml::from_str(content) {
+            Ok(config) => config,
+            Err(e) => {
+                let line = e.span().map(|span| content[..span.start].matches('\n').count() + 1);
+                let at = line.map(|line| format!("line {}: ", line)).unwrap_or_default();
+                return self.warn(path, format!("{}{}", at, e.message().trim().replace('\n', ": ")));
+            }
+        };
+
+        // The top-level `import` is the spelling from before Alacritty 0.14
+        let imports = config.import.into_iter().chain(config.general.and_then(|general| general.import));
+        for import in imports.flatten() {
+            self.load_file(&resolve(path, &import), Self::parse_alacritty);
+        }
+
+        if let Some(colors) = config.colors {
+            let mut entries: Vec<(String, Slot, Option<String>)> = Vec::new();
+            if let Some(primary) = colors.primary {
+                entries.push(("colors.primary.background".into(), Slot::Background, primary.background));
+                entries.push(("colors.primary.foreground".into(), Slot::Foreground, primary.foreground));
+                entries.push(("colors.primary.dim_foreground".into(), Slot::DimForeground, primary.dim_foreground));
+            }
+            if let Some(cursor) = colors.cursor {
+                entries.push(("colors.cursor.cursor".into(), Slot::Cursor, cursor.cursor));
+            }
+            if let Some(selection) = colors.selection {
+                entries.push(("colors.selection.text".into(), Slot::SelectionText, selection.text));
+                entries.push(("colors.selection.background".into(), Slot::SelectionBackground, selection.background));
+            }
+            let sets = [
+                ("normal", Slot::Normal as fn(usize) -> Slot, colors.normal),
+                ("bright", Slot::Bright, colors.bright),
+                ("dim", Slot::Dim, colors.dim),
+            ];
+            for (set, slot, ansi) in sets {
+                for (index, (name, value)) in ansi.map(AlacrittyAnsi::entries).into_iter().flatten().enumerate() {
+                    entries.push((format!("colors.{}.{}", set, name), slot(index), value));
+                }
+            }
+            for (key, slot, value) in entries {
+                if let Some(value) = value {
+                    self.set_color(path, &key, Some(slot), &value);
+                }
+            }
+        }
+
+        if let Some(font) = config.font {
+            if let Some(size) = font.size {
+                self.set_font_size(path, "font.size", &size.to_string());
+            }
+            if let Some(family) = font.normal.and_then(|normal| normal.family) {
+                self.set_font_family(path, "font.normal.family", &family);
+            }
+        }
+    }
+
+    /// `kitty.conf`: `key value` lines, with `include` read in place.
+    fn parse_kitty(&mut self, path: &Path, content: &str) {
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            let (key, value) = line.split_once(char::is_whitespace).unwrap_or((line, ""));
+            let value = value.trim();
+            match key {
+                "include" => self.load_file(&resolve(path, value), Self::parse_kitty),
+                "font_family" => self.set_font_family(path, key, value),
+                "font_size" => self.set_font_size(path, key, value),
+                _ => {
+                    let slot = match key {
+                        "background" => Some(Slot::Background),
+                        "foreground" => Some(Slot::Foreground),
+                        "cursor" => Some(Slot::Cursor),
+                        "selection_foreground" => Some(Slot::SelectionText),
+                        "selection_background" => Some(Slot::SelectionBackground),
+                        _ => key.strip_prefix("color").and_then(|n| n.parse().ok()).and_then(Slot::ansi),
+                    };
+                    self.set_color(path, key, slot, value);
+                }
+            }
+        }
+    }
+
+    /// Ghostty's `key = value` config. Like Ghostty, a `theme` is applied
+    /// under the file's own settings and `config-file`s over them.
+    fn parse_ghostty(&mut self, path: &Path, content: &str) {
+        let entries: Vec<(&str, &str)> = content
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .filter_map(|line| line.split_once('='))
+            .map(|(key, value)| (key.trim(), value.trim().trim_matches('"')))
+            .collect();
+
+        // Light/dark pairs and built-in themes can't be resolved without Ghostty
+        for (_, theme) in entries.iter().filter(|(key, value)| *key == "theme" && !value.contains(',')) {
+            let file = if theme.contains('/') {
+                resolve(path, theme)
+            } else {
+                path.with_file_name("themes").join(theme)
+            };
+            if file.exists() {
+                self.load_file(&file, Self::parse_ghostty);
+            }
+        }
+
+        let mut family_set = false;
+        for &(key, value) in &entries {
+            match key {
+                // Repeated font families are fallbacks for the first
+                "font-family" if !family_set && !value.is_empty() => {
+                    self.set_font_family(path, key, value);
+                    family_set = true;
+                }
+                "font-size" => self.set_font_size(path, key, value),
+                "palette" => {
+                    if let Some((index, color)) = value.split_once('=') {
+                        self.set_color(path, key, index.trim().parse().ok().and_then(Slot::ansi), color.trim());
+                    }
+                }
+                _ => {
+                    let slot = match key {
+                        "background" => Some(Slot::Background),
+                        "foreground" => Some(Slot::Foreground),
+                        "cursor-color" => Some(Slot::Cursor),
+                        "selection-foreground" => Some(Slot::SelectionText),
+                        "selection-background" => Some(Slot::SelectionBackground),
+                        _ => None,
+                    };
+                    self.set_color(path, key, slot, value);
+                }
+            }
+        }
+
+        for &(key, value) in &entries {
+            if key == "config-file" {
+                // A leading `?` only marks the file as optional
+                let file = resolve(path, value.trim_start_matches('?').trim_matches('"'));
+                self.load_file(&file, Self::parse_ghostty);
+            }
+        }
+    }
+
+    /// foot's `foot.ini`: `[section]` headers and `key=value` lines.
+    fn parse_foot(&mut self, path: &Path, content: &str) {
+        let mut section = "main";
+        for line in content.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            if let Some(name) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                section = name.trim();
+                continue;
+            }
+            let Some((key, value)) = line.split_once('=') else { continue };
+            let (key, value) = (key.trim(), value.trim());
+            match (section, key) {
+                (_, "include") => self.load_file(&resolve(path, value), Self::parse_foot),
+                // `Family:size=11, Fallback` with options after the family
+                ("main", "font") => {
+                    let primary = value.split(',').next().unwrap_or_default();
+                    let mut parts = primary.split(':');
+                    if let Some(family) = parts.next().map(str::trim).filter(|family| !family.is_empty()) {
+                        self.set_font_family(path, key, family);
+                    }
+                    for option in parts {
+                        if let Some(size) = option.trim().strip_prefix("size=") {
+                            self.set_font_size(path, key, size);
+                        }
+                    }
+                }
+                // The text color under the cursor, then the cursor's own
+                ("cursor", "color") => {
+                    if let Some(cursor) = value.split_whitespace().nth(1) {
+                        self.set_color(path, "cursor.color", Some(Slot::Cursor), cursor);
+                    }
+                }
+                ("colors" | "colors-dark", _) => {
+                    let ansi = |prefix: &str| key.strip_prefix(prefix).and_then(|n| n.parse::<usize>().ok()).filter(|n| *n < 8);
+                    let slot = match key {
+                        "background" => Some(Slot::Background),
+                        "foreground" => Some(Slot::Foreground),
+                        "selection-foreground" => Some(Slot::SelectionText),
+                        "selection-background" => Some(Slot::SelectionBackground),
+                        _ => ansi("regular")
+                            .map(Slot::Normal)
+                            .or_else(|| ansi("bright").map(Slot::Bright))
+                            .or_else(|| ansi("dim").map(Slot::Dim)),
+                    };
+                    self.set_color(path, key, slot, value);
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+/// An include path as written in `from`: `~` is expanded and relative paths
+/// are relative to `from`'s directory.
+fn resolve(from: &Path, target: &str) -> PathBuf {
+    let expanded = PathBuf::from(shellexpand::tilde(target).as_ref());
+    match from.parent() {
+        Some(dir) if expanded.is_relative() => dir.join(expanded),
+        _ => expanded,
+    }
+}
+
+/// Whether `value` is something `Theme::named_color` understands.
+pub(crate) fn is_color(value: &str) -> bool {
+    Slot::named(value).is_some() || parse_hex_color(value).is_ok()
+}
+
+/// `#rrggbb`, or `#rrggbbaa` when not opaque.
+fn hex(color: egui::Color32) -> String {
+    let [r, g, b, a] = color.to_srgba_unmultiplied();
+    if a == 255 {
+        format!("#{:02x}{:02x}{:02x}", r, g, b)
+    } else {
+        format!("#{:02x}{:02x}{:02x}{:02x}", r, g, b, a)
+    }
+}
+
+/// `#RRGGBB`, `RRGGBB`, `0xRRGGBB`, the short `#RGB` or `#RRGGBBAA` with alpha.
+fn parse_hex_color(value: &str) -> Result<egui::Color32> {
+    let value = value.trim();
+    let hex = value
+        .strip_prefix('#')
+        .or_else(|| value.strip_prefix("0x"))
+        .or_else(|| value.strip_prefix("0X"))
+        .unwrap_or(value);
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        anyhow::bail!("\"{}\" isn't a hex color", value);
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+    match hex.len() {
+        3 => {
+            let short = |i: usize| u8::from_str_radix(&hex[i..=i], 16).map(|v| v * 17);
+            Ok(egui::Color32::from_rgb(short(0)?, short(1)?, short(2)?))
+        }
+        6 => Ok(egui::Color32::from_rgb(channel(0)?, channel(2)?, channel(4)?)),
+        8 => Ok(egui::Color32::from_rgba_unmultiplied(channel(0)?, channel(2)?, channel(4)?, channel(6)?)),
+        _ => anyhow::bail!("\"{}\" should have 3, 6 or 8 hex digits", value),
+    }
+}
+
+#[derive(Deserialize)]
+struct AlacrittyConfig {
+    import: Option<Vec<String>>,
+    colors: Option<AlacrittyColors>,
+    general: Option<AlacrittyGeneral>,
+    font: Option<AlacrittyFont>,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyFont {
+    normal: Option<AlacrittyFontFamily>,
+    size: Option<f32>,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyFontFamily {
+    family: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyGeneral {
+    import: Option<Vec<String>>,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyColors {
+    primary: Option<AlacrittyPrimary>,
+    cursor: Option<AlacrittyCursor>,
+    selection: Option<AlacrittySelection>,
+    normal: Option<AlacrittyAnsi>,
+    bright: Option<AlacrittyAnsi>,
+    dim: Option<AlacrittyAnsi>,
+}
+
+#[derive(Deserialize)]
+struct AlacrittyPrimary {
+    background: Option<String>,
+    foreground: Option<String>,
+    dim_foreground: Option<String>,
+}
+
+// Alacritty also takes `CellForeground`/`CellBackground` in these, which
+// aren't colors we can use and are skipped like any other invalid value
+#[derive(Deserialize)]
+struct AlacrittyCursor {
+    cursor: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct AlacrittySelection {
+    text: Option<String>,
+    background: Option<String>,
+}
+
+/// One of the `normal`, `bright` and `dim` sets of 8 ANSI colors.
+#[derive(Deserialize)]
+struct AlacrittyAnsi {
+    black: Option<String>,
+    red: Option<String>,
+    green: Option<String>,
+    yellow: Option<String>,
+    blue: Option<String>,
+    magenta: Option<String>,
+    cyan: Option<String>,
+    white: Option<String>,
+}
+
+impl AlacrittyAnsi {
+    /// In ANSI order, black to white.
+    fn entries(self) -> [(&'static str, Option<String>); 8] {
+        [
+            ("black", self.black),
+            ("red", self.red),
+            ("green", self.green),
+            ("yellow", self.yellow),
+            ("blue", self.blue),
+            ("magenta", self.magenta),
+            ("cyan", self.cyan),
+            ("white", self.white),
+        ]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parse(parse: fn(&mut Loader, &Path, &str), content: &str) -> Theme {
+        let mut loader = Loader::default();
+        parse(&mut loader, Path::new("/nonexistent/config"), content);
+        Theme::from_palette(loader.palette)
+    }
+
+    fn load_fixture(name: &str) -> Loader {
+        let mut loader = Loader::default();
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/theme").join(name);
+        loader.load_source(ThemeSource::Alacritty, &path);
+        loader
+    }
+
+    #[test]
+    fn parses_every_color_format() {
+        let color = egui::Color32::from_rgb(0x11, 0x22, 0x33);
+        for value in ["#112233", "112233", "0x112233", "#123"] {
+            assert_eq!(parse_hex_color(value).unwrap(), color, "{}", value);
+        }
+        assert_eq!(parse_hex_color("#11223380").unwrap(), egui::Color32::from_rgba_unmultiplied(0x11, 0x22, 0x33, 0x80));
+        for value in ["#12345", "#gggggg", "CellForeground", ""] {
+            assert!(parse_hex_color(value).is_err(), "{}", value);
+        }
+    }
+
+    #[test]
+    fn follows_alacritty_imports_relative_to_the_importing_file() {
+        let loader = load_fixture("alacritty.toml");
+        let names: Vec<_> = loader.files.iter().filter_map(|f| f.file_name()?.to_str()).collect();
+        assert_eq!(names, ["alacritty.toml", "base.toml", "palette.toml", "missing.toml"]);
+
+        // The cycle back to base.toml and the invalid red
+        assert_eq!(loader.warnings.len(), 2, "{:?}", loader.warnings);
+        assert!(loader.warnings[0].contains("palette.toml: skipped including") && loader.warnings[0].contains("base.toml"));
+        assert!(loader.warnings[1].contains("colors.normal.red"));
+
+        let background = loader.values.iter().find(|value| value.name == "background").unwrap();
+        assert_eq!((background.value.as_str(), background.key.as_str()), ("#101010", "colors.primary.background"));
+        assert!(background.file.ends_with("alacritty.toml"));
+        let selection = loader.values.iter().find(|value| value.name == "selection_background").unwrap();
+        assert_eq!(selection.value, "#ff000080");
+
+        let theme = Theme::from_palette(loader.palette);
+        // The importing file wins over its imports
+        assert_eq!(theme.background, egui::Color32::from_rgb(0x10, 0x10, 0x10));
+        assert_eq!(theme.foreground, egui::Color32::from_rgb(0xee, 0xee, 0xee));
+        assert_eq!(theme.accent, egui::Color32::from_rgb(0, 0, 0xff));
+        assert_eq!(theme.selection_background, Some(egui::Color32::from_rgba_unmultiplied(0xff, 0, 0, 0x80)));
+        assert_eq!(theme.selection_text, None);
+        assert_eq!(theme.font_size, Some(11.0));
+    }
+
+    #[test]
+    fn reports_invalid_toml() {
+        let loader = load_fixture("broken.toml");
+        assert_eq!(loader.warnings.len(), 1);
+        assert!(loader.warnings[0].contains("broken.toml: line 1"), "{}", loader.warnings[0]);
+    }
+
+    #[test]
+    fn reads_kitty_ghostty_and_foot_colors() {
+        let red = egui::Color32::from_rgb(0xff, 0, 0);
+        let blue = egui::Color32::from_rgb(0, 0, 0xff);
+
+        let kitty = parse(Loader::parse_kitty, "# comment\nbackground #ff0000\ncolor4  #0000ff\ncolor9 #ff0000\nfont_size 12.5\n");
+        assert_eq!((kitty.background, kitty.accent, kitty.bright.red), (red, blue, Some(red)));
+        assert_eq!(kitty.font_size, Some(12.5));
+
+        let ghostty = parse(Loader::parse_ghostty, "palette = 4=#0000ff\nfont-family = \"Iosevka\"\nfont-family = Noto\nselection-background = ff0000\n");
+        assert_eq!((ghostty.accent, ghostty.selection_background), (blue, Some(red)));
+        assert_eq!(ghostty.font_family.as_deref(), Some("Iosevka"));
+
+        let foot = parse(Loader::parse_foot, "[main]\nfont=Hack:size=11, Noto\n[cursor]\ncolor=000000 ff0000\n[colors]\nregular4=0000ff\ndim1=ff0000\n");
+        assert_eq!((foot.accent, foot.cursor, foot.dim.red), (blue, Some(red), Some(red)));
+        assert_eq!((foot.font_family.as_deref(), foot.font_size), (Some("Hack"), Some(11.0)));
+    }
+}
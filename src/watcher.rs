@@ -0,0 +1,225 @@
+use std::collections::{HashMap, HashSet};
+use std::ffi::{CString, OsStr, OsString};
+use std::io;
+use std::os::fd::{AsRawFd, FromRawFd, OwnedFd};
+use std::os::unix::ffi::OsStrExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+
+use eframe::egui;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum WatchKind {
+    Todos,
+    Theme,
+    Config,
+}
+
+/// Watches the files the app depends on with inotify and wakes the egui
+/// context when one of them changes, so the UI can stay idle otherwise.
+///
+/// Files are watched through their parent directories because both omado and
+/// most editors save by renaming a new file over the old one, which would end
+/// a watch placed on the file itself.
+///
+/// Dropping the watcher stops its thread, which closes the inotify fd.
+pub(crate) struct Watcher {
+    fd: Arc<OwnedFd>,
+    // Written on drop to wake the thread so it exits
+    stop: Arc<OwnedFd>,
+    shared: Arc<Shared>,
+}
+
+struct Shared {
+    // Watch descriptor -> directory it was added for
+    dirs: Mutex<HashMap<libc::c_int, PathBuf>>,
+    // (directory, entry name) -> what changed if that entry is touched
+    triggers: Mutex<HashMap<(PathBuf, OsString), WatchKind>>,
+    todos_changed: AtomicBool,
+    theme_changed: AtomicBool,
+    config_changed: AtomicBool,
+}
+
+impl Shared {
+    fn flag(&self, kind: WatchKind) -> &AtomicBool {
+        match kind {
+            WatchKind::Todos => &self.todos_changed,
+            WatchKind::Theme => &self.theme_changed,
+            WatchKind::Config => &self.config_changed,
+        }
+    }
+}
+
+const WATCH_MASK: u32 = libc::IN_CLOSE_WRITE
+    | libc::IN_MOVED_TO
+    | libc::IN_MOVED_FROM
+    | libc::IN_CREATE
+    | libc::IN_DELETE
+    | libc::IN_ATTRIB;
+
+impl Watcher {
+    pub fn spawn(ctx: egui::Context) -> io::Result<Self> {
+        let fd = Arc::new(owned_fd(unsafe { libc::inotify_init1(libc::IN_CLOEXEC) })?);
+        let stop = Arc::new(owned_fd(unsafe { libc::eventfd(0, libc::EFD_CLOEXEC) })?);
+
+        let shared = Arc::new(Shared {
+            dirs: Mutex::new(HashMap::new()),
+            triggers: Mutex::new(HashMap::new()),
+            todos_changed: AtomicBool::new(false),
+            theme_changed: AtomicBool::new(false),
+            config_changed: AtomicBool::new(false),
+        });
+
+        let (thread_fd, thread_stop, thread_shared) = (Arc::clone(&fd), Arc::clone(&stop), Arc::clone(&shared));
+        std::thread::Builder::new()
+            .name("omado-watcher".to_string())
+            .spawn(move || read_events(&thread_fd, &thread_stop, &thread_shared, &ctx))?;
+
+        Ok(Self { fd, stop, shared })
+    }
+
+    /// Replaces the set of files watched for `kind`.
+    pub fn watch(&self, kind: WatchKind, files: &[PathBuf]) {
+        let mut triggers = self.shared.triggers.lock().unwrap();
+        triggers.retain(|_, k| *k != kind);
+        for file in files {
+            for trigger in triggers_for(file) {
+                triggers.insert(trigger, kind);
+            }
+        }
+
+        // Re-add every directory, even ones already watched: a symlinked
+        // directory may now point somewhere else and needs a fresh watch.
+        let wanted: HashSet<PathBuf> = triggers.keys().map(|(dir, _)| dir.clone()).collect();
+        let mut dirs = self.shared.dirs.lock().unwrap();
+        let mut fresh = HashMap::new();
+        for dir in wanted {
+            let Ok(c_dir) = CString::new(dir.as_os_str().as_bytes()) else { continue };
+            let wd = unsafe { libc::inotify_add_watch(self.fd.as_raw_fd(), c_dir.as_ptr(), WATCH_MASK) };
+            if wd >= 0 {
+                fresh.insert(wd, dir);
+            }
+        }
+        for wd in dirs.keys() {
+            if !fresh.contains_key(wd) {
+                unsafe { libc::inotify_rm_watch(self.fd.as_raw_fd(), *wd) };
+            }
+        }
+        *dirs = fresh;
+    }
+
+    /// Returns whether files of `kind` changed since the last call.
+    pub fn take_change(&self, kind: WatchKind) -> bool {
+        self.shared.flag(kind).swap(false, Ordering::AcqRel)
+    }
+}
+
+impl Drop for Watcher {
+    fn drop(&mut self) {
+        let one: u64 = 1;
+        unsafe { libc::write(self.stop.as_raw_fd(), (&one as *const u64).cast(), std::mem::size_of::<u64>()) };
+    }
+}
+
+fn owned_fd(fd: libc::c_int) -> io::Result<OwnedFd> {
+    if fd < 0 {
+        return Err(io::Error::last_os_error());
+    }
+    Ok(unsafe { OwnedFd::from_raw_fd(fd) })
+}
+
+/// Directory entries whose modification affects `file`: the file itself,
+/// the file a symlink points to, and every symlinked ancestor directory
+/// (Omarchy switches themes by repointing `~/.config/omarchy/current/theme`).
+fn triggers_for(file: &Path) -> Vec<(PathBuf, OsString)> {
+    let mut triggers = Vec::new();
+    let mut push = |path: &Path| {
+        if let (Some(dir), Some(name)) = (path.parent(), path.file_name()) {
+            let dir = if dir.as_os_str().is_empty() { Path::new(".") } else { dir };
+            triggers.push((dir.to_path_buf(), name.to_os_string()));
+        }
+    };
+
+    push(file);
+    if let Ok(target) = file.canonicalize() {
+        if target != file {
+            push(&target);
+        }
+    }
+    for ancestor in file.ancestors().skip(1) {
+        if ancestor.symlink_metadata().is_ok_and(|m| m.file_type().is_symlink()) {
+            push(ancestor);
+        }
+    }
+    triggers
+}
+
+/// Runs until the watcher is dropped; the fds close once both sides let go.
+fn read_events(fd: &OwnedFd, stop: &OwnedFd, shared: &Shared, ctx: &egui::Context) {
+    let mut buf = [0u8; 4096];
+    let header = std::mem::size_of::<libc::inotify_event>();
+
+    loop {
+        let mut fds = [
+            libc::pollfd { fd: fd.as_raw_fd(), events: libc::POLLIN, revents: 0 },
+            libc::pollfd { fd: stop.as_raw_fd(), events: libc::POLLIN, revents: 0 },
+        ];
+        if unsafe { libc::poll(fds.as_mut_ptr(), fds.len() as libc::nfds_t, -1) } < 0 {
+            if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return;
+        }
+        if fds[1].revents != 0 {
+            return;
+        }
+        if fds[0].revents & libc::POLLIN == 0 {
+            return;
+        }
+
+        let len = unsafe { libc::read(fd.as_raw_fd(), buf.as_mut_ptr().cast(), buf.len()) };
+        if len < 0 {
+            if io::Error::last_os_error().kind() == io::ErrorKind::Interrupted {
+                continue;
+            }
+            return;
+        }
+
+        let len = len as usize;
+        let mut offset = 0;
+        let mut changed = false;
+        while offset + header <= len {
+            let event: libc::inotify_event =
+                unsafe { std::ptr::read_unaligned(buf[offset..].as_ptr().cast()) };
+            let name_start = offset + header;
+            let name_end = (name_start + event.len as usize).min(len);
+            offset = name_end;
+
+            if event.mask & libc::IN_Q_OVERFLOW != 0 {
+                for kind in [WatchKind::Todos, WatchKind::Theme, WatchKind::Config] {
+                    shared.flag(kind).store(true, Ordering::Release);
+                }
+                changed = true;
+                continue;
+            }
+
+            // The name is NUL padded
+            let name = &buf[name_start..name_end];
+            let name = &name[..name.iter().position(|&b| b == 0).unwrap_or(name.len())];
+            if name.is_empty() {
+                continue;
+            }
+
+            let Some(dir) = shared.dirs.lock().unwrap().get(&event.wd).cloned() else { continue };
+            let key = (dir, OsStr::from_bytes(name).to_os_string());
+            let Some(kind) = shared.triggers.lock().unwrap().get(&key).copied() else { continue };
+            shared.flag(kind).store(true, Ordering::Release);
+            changed = true;
+        }
+
+        if changed {
+            ctx.request_repaint();
+        }
+    }
+}